@@ -1,114 +1,64 @@
 use crate::algorithms::algorithm_utils::{
-    apply_errors, calculate_syndrome, generate_random_error_vector,
+    apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error, random_seed,
+    seeded_rng,
 };
-use crate::algorithms::metrics::{AlgorithmMetrics, print_metrics};
-use crate::algorithms::{ball_collision, bjmm, lee_brickell, mmt, patterson, prange, stern};
-use crate::code_generator::generate_code;
-use crate::types::{CodeParams, PartitionParams};
+use crate::algorithms::metrics::print_metrics;
+use crate::algorithms::reaction_attack;
+use crate::algorithms::registry::decoder_by_name;
+use crate::code_generator::{generate_code, generate_qc_code};
+use crate::types::{CodeParams, DecodeInput, DecodeTarget, FlipRule, PartitionParams, PqcatError};
 
+/// Decode a random error on a fresh code and report the outcome. Errors are returned for runs
+/// that cannot start (bad parameters, an unknown algorithm or code type, a failed generation or a
+/// decoder without its key), while a decoder giving up is reported as a failed run
 pub fn run_algorithm(
     algorithm_name: &str,
     code_params: CodeParams,
     partition_params: Option<PartitionParams>,
-) {
-    let (g, h, goppa_params) = generate_code(
+    seed: Option<u64>,
+) -> Result<(), PqcatError> {
+    let decoder = decoder_by_name(algorithm_name, &partition_params.unwrap_or_default())?;
+
+    // Code, error and decoder all draw from one generator, so the seed replays the whole run
+    let seed = seed.unwrap_or_else(random_seed);
+    println!("Seed: {}", seed);
+    let mut rng = seeded_rng(seed);
+
+    let (g, h, structured_key) = generate_code(
         code_params.n,
         code_params.k,
         code_params.w,
         code_params.code_type.clone(),
-    );
+        code_params.circulant_weight,
+        &mut rng,
+    )?;
 
-    let original_error = generate_random_error_vector(code_params.n, code_params.w); // Generate a random error vector of weight w
+    let original_error = generate_random_error_vector(code_params.n, code_params.w, &mut rng)?; // Generate a random error vector of weight w
     println!("Original Error Vector: {:?}", original_error);
 
-    let received_vector = if algorithm_name != "mmt" {
-        let codeword = g.row(0).to_vec();
-        let received_vector = apply_errors(&codeword, &original_error); // Apply errors to a valid codeword
-        println!("Received Vector:       {:?}", received_vector);
-        received_vector
-    } else {
-        Vec::new()
-    };
+    let codeword = g.row_vector(0).to_bits();
+    let received_vector = apply_errors(&codeword, &original_error); // Apply errors to a valid codeword
+    println!("Received Vector:       {:?}", received_vector);
 
-    let (decoded_err, algorithm_metrics) = match algorithm_name {
-        "mmt" => {
-            /*
-            This algorithm, unlike other available here, does not work directly with the corrupted codeword.
-            Instead, it operates in syndrome space so there's no need to generate error vector or apply errors.
-             */
-            if let Some(params) = &partition_params {
-                let p = params.p.unwrap_or(2);
-                let l1 = params.l1.unwrap_or(256);
-                let l2 = params.l2.unwrap_or(256);
-                let s_vec = calculate_syndrome(&original_error, &h);
-                let s_array = ndarray::Array1::from_vec(s_vec);
-                mmt::run_mmt_algorithm(&h, &s_array, code_params.n, code_params.w, p, l1, l2)
-            } else {
-                eprintln!("MMT algorithm requires partition parameters");
-                (
-                    None,
-                    AlgorithmMetrics {
-                        time: 0,
-                        peak_memory: 0,
-                    },
-                )
-            }
-        }
-        _ => match algorithm_name {
-            "prange" => prange::run_prange_algorithm(&received_vector, &h, code_params.w),
-            "stern" => stern::run_stern_algorithm(&received_vector, &h, code_params.w),
-            "lee_brickell" => lee_brickell::run_lee_brickell_algorithm(
-                &received_vector,
-                &h,
-                code_params.n,
-                code_params.w,
-            ),
-            "ball_collision" => ball_collision::run_ball_collision_algorithm(
-                &received_vector,
-                &h,
-                code_params.n,
-                code_params.w,
-            ),
-            "bjmm" => bjmm::run_bjmm_algorithm(&received_vector, &h, code_params.n, code_params.w),
-            "patterson" => {
-                let goppa_params = goppa_params.unwrap();
-                patterson::run_patterson_algorithm(
-                    &received_vector,
-                    &h,
-                    &goppa_params,
-                    code_params.w,
-                )
-            }
-            _ => (
-                None,
-                AlgorithmMetrics {
-                    time: 0,
-                    peak_memory: 0,
-                },
-            ),
-        },
+    let error_syndrome = calculate_syndrome(&original_error, &h);
+    let input = DecodeInput {
+        h: &h,
+        target: DecodeTarget::Received(&received_vector),
+        weight: code_params.w,
+        key: structured_key.as_ref(),
     };
+    let outcome = decoder.decode(&input, &mut rng);
 
     // Print algorithm metrics regardless of success/failure
-    print_metrics(&algorithm_metrics);
+    print_metrics(&outcome.metrics);
 
-    match decoded_err {
-        Some(decoded_error) => {
+    match outcome.result {
+        Ok(decoded_error) => {
             println!("Decoded Error Vector:  {:?}", decoded_error);
 
-            // Check if applying this error corrects the received vector to a valid codeword
-            let corrected = received_vector
-                .iter()
-                .zip(decoded_error.iter())
-                .map(|(&r, &e)| r ^ e)
-                .collect::<Vec<u8>>();
-
-            let corrected_syndrome = calculate_syndrome(&corrected, &h);
-
-            // Check weight constraint
-            let decoded_weight = decoded_error.iter().filter(|&&bit| bit == 1).count();
-
-            if corrected_syndrome.iter().all(|&x| x == 0) && decoded_weight <= code_params.w {
+            // Check if applying this error corrects the received vector to a valid codeword,
+            // i.e. if it has the same syndrome as the original error, within the weight constraint
+            if is_valid_error(&decoded_error, &error_syndrome, &h, code_params.w) {
                 println!("Result: success (valid error vector found)");
                 if decoded_error == original_error {
                     println!("[Note: Found the exact original error vector]");
@@ -119,8 +69,67 @@ pub fn run_algorithm(
                 println!("Result: failure (invalid error vector)");
             }
         }
-        None => {
-            println!("Result: failure (algorithm did not find an error vector)");
-        }
+        Err(e @ PqcatError::Unsupported(_)) => return Err(e),
+        Err(e) => println!("Result: failure ({})", e),
     }
+
+    Ok(())
+}
+
+// Reaction attack on a fresh QC-MDPC key: the oracle decodes with the given bit-flipping decoder
+// and the code's w is the weight of the crafted errors
+pub fn run_reaction_attack(
+    code_params: CodeParams,
+    circulant_weight: usize,
+    decoder: &str,
+    queries_per_distance: usize,
+    seed: Option<u64>,
+) -> Result<(), PqcatError> {
+    let rule = FlipRule::from_name(decoder).ok_or_else(|| {
+        PqcatError::Unsupported(format!("{} is not a bit-flipping decoder", decoder))
+    })?;
+    let seed = seed.unwrap_or_else(random_seed);
+    println!("Seed: {}", seed);
+    let mut rng = seeded_rng(seed);
+
+    let (g, _h, key) = generate_qc_code(code_params.n, code_params.k, circulant_weight, &mut rng)?;
+    println!("Secret h0 support:     {:?}", key.blocks[0].support());
+
+    let (report, algorithm_metrics) = reaction_attack::run_reaction_attack(
+        &g,
+        &key,
+        rule,
+        code_params.w,
+        queries_per_distance,
+        &mut rng,
+    )?;
+
+    print_metrics(&algorithm_metrics);
+    println!(
+        "Decryption failures: {} / {} queries",
+        report.failures, report.queries
+    );
+    let lowest = report
+        .failure_rates
+        .iter()
+        .copied()
+        .fold(f64::INFINITY, f64::min);
+    let highest = report.failure_rates.iter().copied().fold(0.0, f64::max);
+    println!("Failure rate by distance: {:.4} to {:.4}", lowest, highest);
+    println!(
+        "Distance spectrum: {} distances classified as present, {} misclassified",
+        report.spectrum.len(),
+        report.spectrum_errors
+    );
+    match &report.recovered_h0 {
+        Some(h0) => println!("Recovered h0 support:  {:?}", h0.support()),
+        None => println!("Recovered h0 support:  none"),
+    }
+    if report.success {
+        println!("Result: success (h0 recovered up to rotation)");
+    } else {
+        println!("Result: failure (h0 not recovered)");
+    }
+
+    Ok(())
 }
@@ -1,21 +1,38 @@
+use crate::types::{BinaryMatrix, BinaryVector, PqcatError};
 use itertools::Itertools;
-use ndarray::Array2;
-use rand::rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng, rng};
 
-pub fn generate_random_error_vector(n: usize, weight: usize) -> Vec<u8> {
-    assert!(
-        weight <= n,
-        "Weight must be less than or equal to the length of the vector"
-    );
+/// Fresh seed for runs that were not given one, to be reported so they can be replayed
+pub fn random_seed() -> u64 {
+    rng().random()
+}
+
+/// The generator behind every random choice of a run: code, error vector and decoder
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+pub fn generate_random_error_vector(
+    n: usize,
+    weight: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<u8>, PqcatError> {
+    if weight > n {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Error weight ({}) must be at most the length of the vector ({})",
+            weight, n
+        )));
+    }
 
     let mut indices: Vec<usize> = (0..n).collect();
-    indices.shuffle(&mut rng());
+    indices.shuffle(rng);
     let mut error_vector = vec![0; n];
     for &i in indices.iter().take(weight) {
         error_vector[i] = 1;
     }
-    error_vector
+    Ok(error_vector)
 }
 
 pub fn apply_errors(codeword: &[u8], error_vector: &[u8]) -> Vec<u8> {
@@ -26,19 +43,20 @@ pub fn apply_errors(codeword: &[u8], error_vector: &[u8]) -> Vec<u8> {
         .collect()
 }
 
-pub fn calculate_syndrome(error_vector: &[u8], h: &Array2<u8>) -> Vec<u8> {
-    let syndrome: Vec<u8> = h
-        .outer_iter()
-        .map(|row| {
-            let row_vec: Vec<u8> = row.to_owned().to_vec();
-            row_vec
-                .iter()
-                .zip(error_vector.iter())
-                .map(|(r, e)| r & e) // Perform element-wise dot product (mod 2)
-                .fold(0, |acc, x| acc ^ x) // XOR all elements to get the syndrome
-        })
-        .collect();
-    syndrome
+pub fn calculate_syndrome(error_vector: &[u8], h: &BinaryMatrix) -> BinaryVector {
+    h.mul_vector(&BinaryVector::from_bits(error_vector)) // S = H * E^T over GF(2)
+}
+
+/// A decoding is valid if the error has the target syndrome and at most the target weight; it
+/// need not be the error that was applied
+pub fn is_valid_error(
+    error_vector: &[u8],
+    target_syndrome: &BinaryVector,
+    h: &BinaryMatrix,
+    weight: usize,
+) -> bool {
+    let decoded_weight = error_vector.iter().filter(|&&bit| bit == 1).count();
+    decoded_weight <= weight && calculate_syndrome(error_vector, h) == *target_syndrome
 }
 
 pub fn generate_subsets(indices: &[usize], size: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
@@ -46,14 +64,26 @@ pub fn generate_subsets(indices: &[usize], size: usize) -> impl Iterator<Item =
 }
 
 /// Calculate syndrome contribution from a subset of columns
-pub fn calculate_partial_syndrome(h: &Array2<u8>, indices: &[usize], r: usize) -> Vec<u8> {
-    let mut syndrome = vec![0; r];
+/// `h_columns` is the column-major view of H (i.e. `h.transpose()`), so each column is one XOR of words
+pub fn calculate_partial_syndrome(h_columns: &BinaryMatrix, indices: &[usize]) -> BinaryVector {
+    let mut syndrome = BinaryVector::zeros(h_columns.cols);
 
     for &idx in indices {
-        for j in 0..r {
-            syndrome[j] ^= h[[j, idx]];
+        for (s, c) in syndrome.words.iter_mut().zip(h_columns.row(idx)) {
+            *s ^= c;
         }
     }
 
     syndrome
 }
+
+/// Natural logarithm of the binomial coefficient C(n, k), usable far beyond u64 range
+pub fn log_binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    let k = k.min(n - k);
+    (0..k)
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum()
+}
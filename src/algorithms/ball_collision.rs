@@ -1,30 +1,37 @@
-use crate::algorithms::algorithm_utils::calculate_syndrome;
-use crate::algorithms::config::{LIST_SIZE, MAX_ITERATIONS};
+use crate::algorithms::algorithm_utils::{calculate_partial_syndrome, calculate_syndrome};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::Array2;
+use crate::types::{
+    BallCollision, BinaryMatrix, BinaryVector, DecodeInput, DecodeOutcome, PqcatError,
+};
 use rand::prelude::IndexedRandom;
-use rand::{rng, seq::SliceRandom};
+use rand::rngs::StdRng;
+use rand::{Rng, seq::SliceRandom};
 use std::collections::HashMap;
 use std::time::Instant;
 
+const LIST_SIZE: usize = 512; // Random subsets sampled per list
+
 pub fn run_ball_collision_algorithm(
     received_vector: &[u8],
-    h: &Array2<u8>,
+    h: &BinaryMatrix,
     n: usize,
     weight: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     let target_syndrome = calculate_syndrome(received_vector, h);
     update_peak_memory(start_memory, &mut peak_memory);
-    let r = h.shape()[0];
+    let h_columns = h.transpose(); // Column-major view for fast partial syndromes
 
-    for _iteration in 0..MAX_ITERATIONS {
+    for iteration in 0..MAX_ITERATIONS {
         // Split indices into two parts
         let mut indices: Vec<usize> = (0..n).collect();
-        indices.shuffle(&mut rng());
+        indices.shuffle(rng);
 
         let half = n / 2;
         let part1: Vec<usize> = indices[0..half].to_vec();
@@ -35,12 +42,11 @@ pub fn run_ball_collision_algorithm(
         let p2 = weight - p1; // Second half weight
 
         // Generate first list
-        let mut list1: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+        let mut list1: HashMap<BinaryVector, Vec<usize>> = HashMap::new();
         for _ in 0..LIST_SIZE {
             // Select random positions from part1
-            let mut rng = rng();
             let selected_indices = part1
-                .choose_multiple(&mut rng, p1.min(part1.len()))
+                .choose_multiple(rng, p1.min(part1.len()))
                 .cloned()
                 .collect::<Vec<usize>>();
 
@@ -49,12 +55,7 @@ pub fn run_ball_collision_algorithm(
             }
 
             // Calculate partial syndrome
-            let mut partial_syndrome = vec![0; r];
-            for &idx in &selected_indices {
-                for j in 0..r {
-                    partial_syndrome[j] ^= h[[j, idx]];
-                }
-            }
+            let partial_syndrome = calculate_partial_syndrome(&h_columns, &selected_indices);
 
             // Store indices for this syndrome
             list1.insert(partial_syndrome, selected_indices);
@@ -63,9 +64,8 @@ pub fn run_ball_collision_algorithm(
         // Generate second list and check for collisions
         for _ in 0..LIST_SIZE {
             // Select random positions from part2
-            let mut rng = rng();
             let selected_indices = part2
-                .choose_multiple(&mut rng, p2.min(part2.len()))
+                .choose_multiple(rng, p2.min(part2.len()))
                 .cloned()
                 .collect::<Vec<usize>>();
 
@@ -74,18 +74,10 @@ pub fn run_ball_collision_algorithm(
             }
 
             // Calculate partial syndrome
-            let mut partial_syndrome = vec![0; r];
-            for &idx in &selected_indices {
-                for j in 0..r {
-                    partial_syndrome[j] ^= h[[j, idx]];
-                }
-            }
+            let partial_syndrome = calculate_partial_syndrome(&h_columns, &selected_indices);
 
             // Calculate what we need from list1 to match target
-            let mut needed_syndrome = vec![0; r];
-            for i in 0..r {
-                needed_syndrome[i] = target_syndrome[i] ^ partial_syndrome[i];
-            }
+            let needed_syndrome = target_syndrome.xor(&partial_syndrome);
 
             // Look for matching syndrome in list1
             if let Some(indices1) = list1.get(&needed_syndrome) {
@@ -108,9 +100,11 @@ pub fn run_ball_collision_algorithm(
                     let metrics = AlgorithmMetrics {
                         time: start_time.elapsed().as_micros() as usize,
                         peak_memory,
+                        iterations: iteration + 1,
+                        success_probability: None,
                     };
 
-                    return (Some(candidate_error), metrics);
+                    return (Ok(candidate_error), metrics);
                 }
             }
         }
@@ -121,7 +115,22 @@ pub fn run_ball_collision_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: MAX_ITERATIONS,
+        success_probability: None,
     };
 
-    (None, metrics)
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for BallCollision {
+    fn name(&self) -> &'static str {
+        "ball_collision"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_ball_collision_algorithm(received, input.h, input.h.cols, input.weight, rng)
+        })
+    }
 }
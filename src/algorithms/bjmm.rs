@@ -1,169 +1,239 @@
-use crate::algorithms::algorithm_utils::{calculate_partial_syndrome, calculate_syndrome};
-use crate::algorithms::config::{LIST_SIZE, MAX_ITERATIONS};
+use crate::algorithms::algorithm_utils::{
+    calculate_partial_syndrome, calculate_syndrome, generate_subsets, log_binomial,
+};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::Array2;
-use rand::prelude::IndexedRandom;
-use rand::{rng, seq::SliceRandom};
-use std::collections::HashMap;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, Bjmm, DecodeInput, DecodeOutcome, PqcatError};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
+/// A list element: its syndrome window and the sorted support it stands for
+type Entry = (u64, Vec<usize>);
+
+/// Probability that one iteration succeeds: p errors in the (k+l)-sized window, w-p elsewhere,
+/// and at least one representation surviving each filter, approximated as
+/// min(1, R1 / 2^l1) * min(1, R2 / 2^l2) with R1 = C(p, p/2) C(k+l-p, ε1), R2 = C(p1, p1/2) C(k+l-p1, ε2)
+pub fn success_probability(
+    n: usize,
+    k: usize,
+    w: usize,
+    (p, p1, p2): (usize, usize, usize),
+    (l1, l2): (usize, usize),
+) -> f64 {
+    let window = k + l1 + l2;
+    if p > w || window > n || p1 > window {
+        return 0.0;
+    }
+    let surviving =
+        |representations: f64, bits: usize| (representations - bits as f64 * 2f64.ln()).min(0.0);
+    let r1 = log_binomial(p, p / 2) + log_binomial(window - p, p1 - p / 2);
+    let r2 = log_binomial(p1, p1 / 2) + log_binomial(window - p1, p2 - p1 / 2);
+    (log_binomial(window, p) + log_binomial(n - window, w - p) - log_binomial(n, w)
+        + surviving(r1, l1)
+        + surviving(r2, l2))
+    .exp()
+}
+
+fn low_bits(value: u64, bits: usize) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        value & ((1u64 << bits) - 1)
+    }
+}
+
+/// Sum of two sorted supports over GF(2): shared positions cancel out (1 + 1 = 0)
+fn symmetric_difference(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut result = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            result.push(a[i]);
+            i += 1;
+        } else if a[i] > b[j] {
+            result.push(b[j]);
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    result.extend_from_slice(&a[i..]);
+    result.extend_from_slice(&b[j..]);
+    result
+}
+
+/// Join two lists on their low `bits` window bits so that the keys add up to `target`.
+/// Only sums of exactly `weight` are kept, and each support once even if several
+/// representations lead to it
+fn merge(left: &[Entry], right: &[Entry], bits: usize, target: u64, weight: usize) -> Vec<Entry> {
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, (key, _)) in right.iter().enumerate() {
+        index.entry(low_bits(*key, bits)).or_default().push(idx);
+    }
+
+    let mut seen: HashSet<Vec<usize>> = HashSet::new();
+    let mut merged = Vec::new();
+    for (left_key, left_support) in left {
+        let Some(matches) = index.get(&low_bits(left_key ^ target, bits)) else {
+            continue;
+        };
+        for &idx in matches {
+            let (right_key, right_support) = &right[idx];
+            let support = symmetric_difference(left_support, right_support);
+            if support.len() == weight && seen.insert(support.clone()) {
+                merged.push((left_key ^ right_key, support));
+            }
+        }
+    }
+    merged
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run_bjmm_algorithm(
     received_vector: &[u8],
-    h: &Array2<u8>,
-    n: usize,
+    h: &BinaryMatrix,
     weight: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    p: usize,
+    eps1: usize,
+    eps2: usize,
+    l1: usize,
+    l2: usize,
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     let target_syndrome = calculate_syndrome(received_vector, h);
     update_peak_memory(start_memory, &mut peak_memory);
-    let r = h.shape()[0];
-    let mut rng = rng();
-
-    for _iteration in 0..MAX_ITERATIONS {
-        // Bring parity check matrix to systematic form (permute columns)
-        let mut indices: Vec<usize> = (0..n).collect();
-        indices.shuffle(&mut rng);
-
-        // Partition the indices for a 4-way split as per BJMM
-        let quarter = n / 4;
-        let part1: Vec<usize> = indices[0..quarter].to_vec();
-        let part2: Vec<usize> = indices[quarter..2 * quarter].to_vec();
-        let part3: Vec<usize> = indices[2 * quarter..3 * quarter].to_vec();
-        let part4: Vec<usize> = indices[3 * quarter..n].to_vec();
-
-        // Split the weight roughly into 4 parts
-        let w1 = weight / 4;
-        let w2 = weight / 4;
-        let w3 = weight / 4;
-        let w4 = weight - w1 - w2 - w3;
-
-        // Build intermediate representation lists (first level)
-
-        let mut list_a: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-        for _ in 0..LIST_SIZE {
-            let selected_indices = part1
-                .choose_multiple(&mut rng, w1.min(part1.len()))
-                .cloned()
-                .collect::<Vec<usize>>();
-
-            let representation = calculate_partial_syndrome(h, &selected_indices, r);
-
-            list_a
-                .entry(representation)
-                .or_default()
-                .push(selected_indices);
-        }
+    let (r, n) = h.dim();
+    let mut columns: Vec<usize> = (0..n).collect();
 
-        let mut list_b: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-        for _ in 0..LIST_SIZE {
-            let selected_indices = part2
-                .choose_multiple(&mut rng, w2.min(part2.len()))
-                .cloned()
-                .collect::<Vec<usize>>();
+    // Weights per level: e2 = sum of two weight-p1 vectors, each a sum of two weight-p2 vectors.
+    // Sums of two equal-weight vectors have even weight, so p and p1 are rounded to even values
+    let p = p.min(weight) & !1;
+    let p1 = (p / 2 + eps1).next_multiple_of(2);
+    let p2 = p1 / 2 + eps2;
+    let (left_weight, right_weight) = (p2.div_ceil(2), p2 / 2);
+
+    // Level 2 fixes l2 bits, level 1 extends that to l1 bits, the top merge covers all l1 + l2
+    let max_window = r.min(64);
+    if l2 > l1 || l1 + l2 > max_window {
+        let metrics = AlgorithmMetrics {
+            time: start_time.elapsed().as_micros() as usize,
+            peak_memory,
+            iterations: 0,
+            success_probability: None,
+        };
+        let message = format!(
+            "BJMM needs l2 <= l1 and l1 + l2 <= {} (min of r = {} and 64), got l1 = {}, l2 = {}",
+            max_window, r, l1, l2
+        );
+        return (Err(PqcatError::InvalidParameters(message)), metrics);
+    }
+    let l = l1 + l2;
 
-            let representation = calculate_partial_syndrome(h, &selected_indices, r);
+    let mut loop_count = 0;
+    let mut reduced_rows = r - l;
 
-            list_b
-                .entry(representation)
-                .or_default()
-                .push(selected_indices);
+    while loop_count < MAX_ITERATIONS {
+        loop_count += 1;
+
+        // Partial Gaussian elimination leaving an l x (k+l) block H2 on the non-pivot columns
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut syndrome = target_syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut syndrome), &columns, r - l);
+        reduced_rows = pivots.len();
+        let window = (r - reduced_rows).min(64);
+
+        let mut is_pivot = vec![false; n];
+        for &col in &pivots {
+            is_pivot[col] = true;
         }
+        let window_columns: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+
+        let h_columns = h_reduced.transpose();
+        let column_window: Vec<u64> = (0..n)
+            .map(|j| h_columns.row_vector(j).window(reduced_rows, window))
+            .collect();
+        let target = syndrome.window(reduced_rows, window);
 
-        // Build second-level representation lists by merging
+        // ===== Level 3: base lists on two disjoint halves of the window =====
 
-        let mut list_c: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-        for _ in 0..LIST_SIZE {
-            let selected_indices = part3
-                .choose_multiple(&mut rng, w3.min(part3.len()))
-                .cloned()
-                .collect::<Vec<usize>>();
+        let base_list = |half: &[usize], w: usize| -> Vec<Entry> {
+            generate_subsets(half, w)
+                .map(|mut subset| {
+                    subset.sort_unstable();
+                    let key = subset.iter().fold(0, |acc, &j| acc ^ column_window[j]);
+                    (key, subset)
+                })
+                .collect()
+        };
 
-            let representation = calculate_partial_syndrome(h, &selected_indices, r);
+        // ===== Level 2: four weight-p2 lists on l2 bits, targets u1..u4 summing to s =====
 
-            list_c
-                .entry(representation)
-                .or_default()
-                .push(selected_indices);
+        // Every list draws its own split, otherwise overlaps could only ever cancel inside one half
+        let u: [u64; 3] = [(); 3].map(|_| low_bits(rng.random::<u64>(), l2));
+        let u4 = low_bits(target, l2) ^ u[0] ^ u[1] ^ u[2];
+        let mut level_2: Vec<Vec<Entry>> = Vec::with_capacity(4);
+        for t in [u[0], u[1], u[2], u4] {
+            let mut split = window_columns.clone();
+            split.shuffle(rng);
+            let (left_half, right_half) = split.split_at(split.len() / 2);
+            let left_base = base_list(left_half, left_weight);
+            let right_base = base_list(right_half, right_weight);
+            level_2.push(merge(&left_base, &right_base, l2, t, p2));
         }
+        update_peak_memory(start_memory, &mut peak_memory);
 
-        let mut list_d: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-        for _ in 0..LIST_SIZE {
-            let selected_indices = part4
-                .choose_multiple(&mut rng, w4.min(part4.len()))
-                .cloned()
-                .collect::<Vec<usize>>();
+        // ===== Level 1: two weight-p1 lists on l1 bits, overlaps allowed =====
 
-            let representation = calculate_partial_syndrome(h, &selected_indices, r);
+        // The low l2 bits of v are already fixed by u1 + u2, the rest is chosen at random
+        let v = low_bits(rng.random::<u64>(), l1) & !low_bits(u64::MAX, l2) | (u[0] ^ u[1]);
+        let list_1 = merge(&level_2[0], &level_2[1], l1, v, p1);
+        let list_2 = merge(&level_2[2], &level_2[3], l1, low_bits(target, l1) ^ v, p1);
+        update_peak_memory(start_memory, &mut peak_memory);
 
-            list_d
-                .entry(representation)
-                .or_default()
-                .push(selected_indices);
-        }
+        // ===== Top: weight-p candidates matching the whole window =====
+
+        for (_, e2_support) in merge(&list_1, &list_2, l, target, p) {
+            // e1 = s1 + H1·e2 sits on the identity part and must have weight w - p
+            let residual = syndrome.xor(&calculate_partial_syndrome(&h_columns, &e2_support));
+            if !residual.slice(reduced_rows..r).is_zero() || residual.weight() > weight - p {
+                continue;
+            }
 
-        // Look for matches between combined representations
-
-        for (rep_a, subsets_a) in &list_a {
-            for (rep_b, subsets_b) in &list_b {
-                // Calculate combined representation for AB
-                let mut rep_ab = rep_a.clone();
-                for i in 0..r {
-                    rep_ab[i] ^= rep_b[i];
-                }
-
-                // Calculate what we need from C and D to match target
-                let mut needed_rep_cd = target_syndrome.clone();
-                for i in 0..r {
-                    needed_rep_cd[i] ^= rep_ab[i];
-                }
-
-                for (rep_c, subsets_c) in &list_c {
-                    // Calculate what we need from list_d
-                    let mut needed_rep_d = needed_rep_cd.clone();
-                    for i in 0..r {
-                        needed_rep_d[i] ^= rep_c[i];
-                    }
-
-                    // Look for this representation in list_d
-                    if let Some(subsets_d) = list_d.get(&needed_rep_d) {
-                        // We found a potential match, try combining representations to form a complete error vector
-                        for subset_a in subsets_a {
-                            for subset_b in subsets_b {
-                                for subset_c in subsets_c {
-                                    for subset_d in subsets_d {
-                                        // Create the combined error vector
-                                        let mut candidate_error = vec![0; n];
-                                        for &idx in subset_a
-                                            .iter()
-                                            .chain(subset_b.iter())
-                                            .chain(subset_c.iter())
-                                            .chain(subset_d.iter())
-                                        {
-                                            candidate_error[idx] = 1;
-                                        }
-
-                                        let check_syndrome =
-                                            calculate_syndrome(&candidate_error, h);
-                                        if check_syndrome == target_syndrome {
-                                            update_peak_memory(start_memory, &mut peak_memory);
-
-                                            let metrics = AlgorithmMetrics {
-                                                time: start_time.elapsed().as_micros() as usize,
-                                                peak_memory,
-                                            };
-
-                                            return (Some(candidate_error), metrics);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            let mut candidate_error = vec![0; n];
+            for &idx in &e2_support {
+                candidate_error[idx] = 1;
             }
+            for (row, &col) in pivots.iter().enumerate() {
+                candidate_error[col] = residual.get(row);
+            }
+
+            update_peak_memory(start_memory, &mut peak_memory);
+
+            let metrics = AlgorithmMetrics {
+                time: start_time.elapsed().as_micros() as usize,
+                peak_memory,
+                iterations: loop_count,
+                success_probability: Some(success_probability(
+                    n,
+                    n - reduced_rows - l,
+                    weight,
+                    (p, p1, p2),
+                    (l1, l2),
+                )),
+            };
+
+            return (Ok(candidate_error), metrics);
         }
     }
 
@@ -172,7 +242,82 @@ pub fn run_bjmm_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(
+            n,
+            n - reduced_rows - l,
+            weight,
+            (p, p1, p2),
+            (l1, l2),
+        )),
+    };
+
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for Bjmm {
+    fn name(&self) -> &'static str {
+        "bjmm"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_bjmm_algorithm(
+                received,
+                input.h,
+                input.weight,
+                self.p,
+                self.eps1,
+                self.eps2,
+                self.l1,
+                self.l2,
+                rng,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
     };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_a_planted_error() {
+        // A random [23, 12] code, three errors on a random codeword
+        let (n, k, w) = (23, 12, 3);
+        let mut rng = StdRng::seed_from_u64(6);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
 
-    (None, metrics)
+        let decoded = Bjmm {
+            p: 2,
+            eps1: 1,
+            eps2: 0,
+            l1: 2,
+            l2: 1,
+        }
+        .decode(&input, &mut rng)
+        .result
+        .unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
 }
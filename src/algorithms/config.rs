@@ -1,2 +1 @@
 pub const MAX_ITERATIONS: usize = 100;
-pub const LIST_SIZE: usize = 512;
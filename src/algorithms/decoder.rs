@@ -0,0 +1,104 @@
+use crate::algorithms::algorithm_utils::calculate_syndrome;
+use crate::algorithms::metrics::AlgorithmMetrics;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryVector, DecodeInput, DecodeOutcome, DecodeTarget, PqcatError};
+use rand::rngs::StdRng;
+use std::borrow::Cow;
+
+/// A decoding algorithm: given H, a received word or a syndrome and the number of errors, find an
+/// error vector of that weight with the right syndrome. All randomness comes from `rng`, so a
+/// decoding is replayed exactly by reseeding it
+pub trait Decoder {
+    fn name(&self) -> &'static str;
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome;
+}
+
+impl DecodeInput<'_> {
+    pub fn syndrome(&self) -> Cow<'_, BinaryVector> {
+        match self.target {
+            DecodeTarget::Received(received) => Cow::Owned(calculate_syndrome(received, self.h)),
+            DecodeTarget::Syndrome(syndrome) => Cow::Borrowed(syndrome),
+        }
+    }
+
+    /// A word to decode: the received vector itself, or for a syndrome s any x with H x = s, which
+    /// lies in the same coset and so has the same error vectors. None if s has no preimage
+    pub fn received(&self) -> Option<Cow<'_, [u8]>> {
+        let syndrome = match self.target {
+            DecodeTarget::Received(received) => return Some(Cow::Borrowed(received)),
+            DecodeTarget::Syndrome(syndrome) => syndrome,
+        };
+
+        let (rows, n) = self.h.dim();
+        let mut h_reduced = self.h.clone();
+        let mut reduced_syndrome = syndrome.clone();
+        let column_order: Vec<usize> = (0..n).collect();
+        let pivots = row_reduce(
+            &mut h_reduced,
+            Some(&mut reduced_syndrome),
+            &column_order,
+            rows,
+        );
+        if (pivots.len()..rows).any(|i| reduced_syndrome.get(i) == 1) {
+            return None;
+        }
+
+        let mut received = vec![0; n];
+        for (row, &col) in pivots.iter().enumerate() {
+            received[col] = reduced_syndrome.get(row);
+        }
+        Some(Cow::Owned(received))
+    }
+}
+
+impl DecodeOutcome {
+    /// Wrap the result of a run_*_algorithm function
+    pub fn from_run((result, metrics): (Result<Vec<u8>, PqcatError>, AlgorithmMetrics)) -> Self {
+        DecodeOutcome { result, metrics }
+    }
+
+    /// Outcome of a decoder that gave up before running
+    pub fn failed(error: PqcatError) -> Self {
+        DecodeOutcome {
+            result: Err(error),
+            metrics: AlgorithmMetrics {
+                time: 0,
+                peak_memory: 0,
+                iterations: 0,
+                success_probability: None,
+            },
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn iterations(&self) -> usize {
+        self.metrics.iterations
+    }
+
+    #[allow(dead_code)]
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Run a decoder that works on received vectors, turning a syndrome target into a preimage first
+pub fn decode_received(
+    input: &DecodeInput,
+    run: impl FnOnce(&[u8]) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics),
+) -> DecodeOutcome {
+    match input.received() {
+        Some(received) => DecodeOutcome::from_run(run(&received)),
+        None => DecodeOutcome::failed(PqcatError::Undecodable(
+            "the syndrome is not in the column space of H".to_string(),
+        )),
+    }
+}
+
+/// Error of a decoder run without the private key it needs
+pub fn missing_key(decoder: &dyn Decoder, key_type: &str) -> PqcatError {
+    PqcatError::Unsupported(format!(
+        "{} requires a {} private key",
+        decoder.name(),
+        key_type
+    ))
+}
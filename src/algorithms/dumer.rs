@@ -0,0 +1,219 @@
+use crate::algorithms::algorithm_utils::{
+    calculate_partial_syndrome, calculate_syndrome, generate_subsets, log_binomial,
+};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
+use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, BinaryVector, DecodeInput, DecodeOutcome, Dumer, PqcatError};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::time::Instant;
+
+/// Probability that one iteration succeeds: exactly p errors in each half of the (k+l)-sized
+/// window and w-2p on the remaining n-k-l positions
+pub fn success_probability(n: usize, k: usize, w: usize, p: usize, l: usize) -> f64 {
+    let window = k + l;
+    let (k1, k2) = (window / 2, window - window / 2);
+    if 2 * p > w || window > n {
+        return 0.0;
+    }
+    (log_binomial(k1, p) + log_binomial(k2, p) + log_binomial(n - window, w - 2 * p)
+        - log_binomial(n, w))
+    .exp()
+}
+
+/// All weight-p subsets of `indices`, keyed by their l-bit syndrome window and sorted by key
+fn sorted_list(
+    h_columns: &BinaryMatrix,
+    indices: &[usize],
+    p: usize,
+    start: usize,
+    l: usize,
+    offset: u64,
+) -> Vec<(u64, Vec<usize>)> {
+    let mut list: Vec<(u64, Vec<usize>)> = generate_subsets(indices, p)
+        .map(|subset| {
+            let key = calculate_partial_syndrome(h_columns, &subset).window(start, l) ^ offset;
+            (key, subset)
+        })
+        .collect();
+    list.sort_unstable_by_key(|(key, _)| *key);
+    list
+}
+
+pub fn run_dumer_algorithm(
+    received_vector: &[u8],
+    h: &BinaryMatrix,
+    weight: usize,
+    p: usize,
+    l: usize,
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
+    let start_time = Instant::now();
+    let start_memory = start_memory_tracking();
+    let mut peak_memory = 0;
+
+    let target_syndrome = calculate_syndrome(received_vector, h);
+    update_peak_memory(start_memory, &mut peak_memory);
+    let (r, n) = h.dim();
+    let mut columns: Vec<usize> = (0..n).collect();
+
+    let p = p.min(weight / 2);
+    let l = l.min(r).min(64); // The window has to fit into a single word
+
+    let mut loop_count = 0;
+    let mut reduced_rows = r - l;
+
+    while loop_count < MAX_ITERATIONS {
+        loop_count += 1;
+
+        // Partial Gaussian elimination: identity on n-k-l columns, leaving an l x (k+l) block H2
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut syndrome = target_syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut syndrome), &columns, r - l);
+        reduced_rows = pivots.len();
+        let window = (r - reduced_rows).min(64);
+
+        // The (k+l)-sized window is every column that did not become a pivot
+        let mut is_pivot = vec![false; n];
+        for &col in &pivots {
+            is_pivot[col] = true;
+        }
+        let mut window_columns: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+        window_columns.shuffle(rng);
+        let (left, right) = window_columns.split_at(window_columns.len() / 2);
+
+        // Sort both lists by H2·e and merge: H2·(e_left + e_right) must equal the bottom syndrome s2
+        let h_columns = h_reduced.transpose();
+        let target_window = syndrome.window(reduced_rows, window);
+        let left_list = sorted_list(&h_columns, left, p, reduced_rows, window, 0);
+        let right_list = sorted_list(&h_columns, right, p, reduced_rows, window, target_window);
+        update_peak_memory(start_memory, &mut peak_memory);
+
+        let (mut i, mut j) = (0, 0);
+        while i < left_list.len() && j < right_list.len() {
+            let (left_key, right_key) = (left_list[i].0, right_list[j].0);
+            if left_key < right_key {
+                i += 1;
+                continue;
+            }
+            if left_key > right_key {
+                j += 1;
+                continue;
+            }
+
+            // Equal keys: walk both runs and try every pair
+            let left_end = (i..left_list.len())
+                .find(|&x| left_list[x].0 != left_key)
+                .unwrap_or(left_list.len());
+            let right_end = (j..right_list.len())
+                .find(|&y| right_list[y].0 != right_key)
+                .unwrap_or(right_list.len());
+
+            for (_, left_subset) in &left_list[i..left_end] {
+                let left_syndrome = calculate_partial_syndrome(&h_columns, left_subset);
+                for (_, right_subset) in &right_list[j..right_end] {
+                    // e1 = s1 + H1·e2 lives on the identity part and must have weight w - 2p
+                    let mut residual: BinaryVector = syndrome.xor(&left_syndrome);
+                    residual.xor_assign(&calculate_partial_syndrome(&h_columns, right_subset));
+
+                    // The bottom rows must vanish too (they exceed the key when H is rank-deficient)
+                    let bottom_clear = residual.slice(reduced_rows..r).is_zero();
+
+                    if bottom_clear && residual.weight() <= weight - 2 * p {
+                        let mut candidate_error = vec![0; n];
+                        for &idx in left_subset.iter().chain(right_subset.iter()) {
+                            candidate_error[idx] = 1;
+                        }
+                        for (row, &col) in pivots.iter().enumerate() {
+                            candidate_error[col] = residual.get(row);
+                        }
+
+                        let metrics = AlgorithmMetrics {
+                            time: start_time.elapsed().as_micros() as usize,
+                            peak_memory,
+                            iterations: loop_count,
+                            success_probability: Some(success_probability(
+                                n,
+                                n - r,
+                                weight,
+                                p,
+                                r - reduced_rows,
+                            )),
+                        };
+
+                        return (Ok(candidate_error), metrics);
+                    }
+                }
+            }
+
+            i = left_end;
+            j = right_end;
+        }
+    }
+
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    let metrics = AlgorithmMetrics {
+        time: start_time.elapsed().as_micros() as usize,
+        peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(n, n - r, weight, p, r - reduced_rows)),
+    };
+
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for Dumer {
+    fn name(&self) -> &'static str {
+        "dumer"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_dumer_algorithm(received, input.h, input.weight, self.p, self.l, rng)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
+    };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_a_planted_error() {
+        // A random [23, 12] code, three errors on a random codeword
+        let (n, k, w) = (23, 12, 3);
+        let mut rng = StdRng::seed_from_u64(4);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
+
+        let decoded = Dumer { p: 1, l: 2 }
+            .decode(&input, &mut rng)
+            .result
+            .unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
+}
@@ -1,93 +1,89 @@
-use crate::algorithms::algorithm_utils::{calculate_syndrome, generate_subsets};
+use crate::algorithms::algorithm_utils::{
+    calculate_partial_syndrome, calculate_syndrome, generate_subsets, log_binomial,
+};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::Array2;
-use rand::rng;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, DecodeInput, DecodeOutcome, LeeBrickell, PqcatError};
+use rand::Rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use std::collections::HashMap;
 use std::time::Instant;
 
+/// Probability that one random information set holds exactly p of the w errors,
+/// i.e. C(k, p) C(n-k, w-p) / C(n, w)
+pub fn success_probability(n: usize, k: usize, w: usize, p: usize) -> f64 {
+    if p > w {
+        return 0.0;
+    }
+    (log_binomial(k, p) + log_binomial(n - k, w - p) - log_binomial(n, w)).exp()
+}
+
 pub fn run_lee_brickell_algorithm(
     received_vector: &[u8],
-    h: &Array2<u8>,
+    h: &BinaryMatrix,
     n: usize,
     weight: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    p: usize,
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     let target_syndrome = calculate_syndrome(received_vector, h);
     update_peak_memory(start_memory, &mut peak_memory);
-    let m = n / 2 + (n % 2);
+    let r = h.rows;
+    let mut columns: Vec<usize> = (0..n).collect();
 
-    /*
-    This algorithm, unlike Stern's or Prange's, generally requires the weight to be even.
-    However, for maximum compatiblity with provided values this check is omitted.
-    Consider always providing even weights to ensure proper use of this algorithm.
+    // p = 0 is exactly Prange; p can't exceed the total weight
+    let p = p.min(weight);
 
-    if weight % 2 != 0 {
-        println!("Weight must be even for Lee-Brickell algorithm.");
-        return None;
-    }
-    */
-
-    // Split indices into left and right halves
-    let indices: Vec<usize> = (0..n).collect();
-    let mut left_indices = indices[..m].to_vec();
-    let mut right_indices = indices[m..].to_vec();
-    left_indices.shuffle(&mut rng());
-    right_indices.shuffle(&mut rng());
-
-    // Create hash maps to store syndrome-to-subset mappings
-    let mut left_map: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
-    let mut right_map: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
-
-    // Generate subsets for both halves and store their syndromes
-    // Left half subsets
-    let left_weight = weight / 2;
-    for subset in generate_subsets(&left_indices, left_weight) {
-        let mut candidate_error = vec![0; n];
-        for &i in &subset {
-            candidate_error[i] = 1;
-        }
-        let syndrome = calculate_syndrome(&candidate_error, h);
-        left_map.insert(syndrome.clone(), subset);
-    }
+    let mut loop_count = 0;
+    let mut rank = r;
 
-    // Right half subsets
-    let right_weight = weight - left_weight;
-    for subset in generate_subsets(&right_indices, right_weight) {
-        let mut candidate_error = vec![0; n];
-        for &i in &subset {
-            candidate_error[i] = 1;
-        }
-        let syndrome = calculate_syndrome(&candidate_error, h);
-        right_map.insert(syndrome.clone(), subset);
-    }
+    while loop_count < MAX_ITERATIONS {
+        loop_count += 1;
+
+        // Systematic form on a random information set
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut syndrome = target_syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut syndrome), &columns, r);
+        rank = pivots.len();
 
-    // Iterate through the left map to find complementary syndromes in the right map
-    for (left_syndrome, left_subset) in &left_map {
-        let mut complement_syndrome = target_syndrome.clone();
-        for (i, &val) in left_syndrome.iter().enumerate() {
-            complement_syndrome[i] ^= val;
+        let mut is_pivot = vec![false; n];
+        for &col in &pivots {
+            is_pivot[col] = true;
         }
-        if let Some(right_subset) = right_map.get(&complement_syndrome) {
-            // Combine the subsets to form the error vector
-            let mut candidate_error = vec![0; n];
-            for &i in left_subset {
-                candidate_error[i] = 1;
-            }
-            for &i in right_subset {
-                candidate_error[i] = 1;
-            }
-            update_peak_memory(start_memory, &mut peak_memory);
+        let info_set: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+        let h_columns = h_reduced.transpose();
+
+        // Guess p error positions inside the information set; the rest must sit on the identity part
+        for subset in generate_subsets(&info_set, p) {
+            let residual = syndrome.xor(&calculate_partial_syndrome(&h_columns, &subset));
+
+            if residual.weight() == weight - p {
+                let mut candidate_error = vec![0; n];
+                for &i in &subset {
+                    candidate_error[i] = 1;
+                }
+                for (row, &col) in pivots.iter().enumerate() {
+                    candidate_error[col] = residual.get(row);
+                }
 
-            let metrics = AlgorithmMetrics {
-                time: start_time.elapsed().as_micros() as usize,
-                peak_memory,
-            };
+                update_peak_memory(start_memory, &mut peak_memory);
 
-            return (Some(candidate_error), metrics);
+                let metrics = AlgorithmMetrics {
+                    time: start_time.elapsed().as_micros() as usize,
+                    peak_memory,
+                    iterations: loop_count,
+                    success_probability: Some(success_probability(n, n - rank, weight, p)),
+                };
+
+                return (Ok(candidate_error), metrics);
+            }
         }
     }
 
@@ -96,7 +92,60 @@ pub fn run_lee_brickell_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(n, n - rank, weight, p)),
     };
 
-    (None, metrics)
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for LeeBrickell {
+    fn name(&self) -> &'static str {
+        "lee_brickell"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_lee_brickell_algorithm(received, input.h, input.h.cols, input.weight, self.p, rng)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
+    };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_a_planted_error() {
+        // A random [23, 12] code, three errors on a random codeword
+        let (n, k, w) = (23, 12, 3);
+        let mut rng = StdRng::seed_from_u64(3);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
+
+        let decoded = LeeBrickell { p: 2 }
+            .decode(&input, &mut rng)
+            .result
+            .unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
 }
@@ -3,6 +3,8 @@ use memory_stats::memory_stats;
 pub struct AlgorithmMetrics {
     pub time: usize,
     pub peak_memory: usize,
+    pub iterations: usize, // Number of main-loop iterations (e.g. information sets tried)
+    pub success_probability: Option<f64>, // Theoretical per-iteration success probability, if known
 }
 
 /// Get initial memory usage
@@ -29,4 +31,12 @@ pub fn update_peak_memory(start_memory: usize, current_peak: &mut usize) {
 pub fn print_metrics(metrics: &AlgorithmMetrics) {
     println!("Time: {} μs", metrics.time);
     println!("Peak memory: {} KiB", metrics.peak_memory / 1024);
+    println!("Iterations: {}", metrics.iterations);
+    if let Some(p) = metrics.success_probability {
+        println!(
+            "Theoretical success probability per iteration: {:.6e} (expected iterations: {:.1})",
+            p,
+            1.0 / p
+        );
+    }
 }
@@ -1,170 +1,226 @@
+use crate::algorithms::algorithm_utils::{
+    calculate_partial_syndrome, generate_subsets, log_binomial,
+};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::Decoder;
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::{Array1, Array2};
-use rand::prelude::IndexedRandom;
-use rand::rng;
-use std::collections::HashMap;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, BinaryVector, DecodeInput, DecodeOutcome, Mmt, PqcatError};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
+/// Probability that one iteration succeeds: p/2 errors in each half of the (k+l)-sized window,
+/// w-p on the remaining positions, and at least one of the C(p/2, p/4)^2 representations
+/// surviving the l1-bit filter (approximated as min(1, R / 2^l1))
+pub fn success_probability(n: usize, k: usize, w: usize, p: usize, l1: usize, l2: usize) -> f64 {
+    let window = k + l1 + l2;
+    let (k1, k2) = (window / 2, window - window / 2);
+    if p > w || window > n {
+        return 0.0;
+    }
+    let half = p / 2;
+    let representations =
+        (log_binomial(half, half.div_ceil(2)) + log_binomial(half, half / 2)).exp();
+    let surviving = (representations / 2f64.powi(l1 as i32)).min(1.0);
+    (log_binomial(k1, half) + log_binomial(k2, half) + log_binomial(n - window, w - p)
+        - log_binomial(n, w))
+    .exp()
+        * surviving
+}
+
+fn low_bits(value: u64, bits: usize) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        value & ((1u64 << bits) - 1)
+    }
+}
+
+/// First-level merge: pairs (x, y) from the two base lists whose l1-bit window sums to `target`
+fn merge_on_target(
+    left: &[(u64, Vec<usize>)],
+    right: &[(u64, Vec<usize>)],
+    l1: usize,
+    target: u64,
+) -> Vec<(u64, Vec<usize>)> {
+    let mut right_index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, (key, _)) in right.iter().enumerate() {
+        right_index.entry(low_bits(*key, l1)).or_default().push(idx);
+    }
+
+    let mut merged = Vec::new();
+    for (left_key, left_subset) in left {
+        let needed = low_bits(left_key ^ target, l1);
+        if let Some(matches) = right_index.get(&needed) {
+            for &idx in matches {
+                let (right_key, right_subset) = &right[idx];
+                let mut support = left_subset.clone();
+                support.extend_from_slice(right_subset);
+                merged.push((left_key ^ right_key, support));
+            }
+        }
+    }
+    merged
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run_mmt_algorithm(
-    h: &Array2<u8>,
-    syndrome: &Array1<u8>,
+    h: &BinaryMatrix,
+    syndrome: &BinaryVector,
     n: usize,
     weight: usize,
     p: usize,
     l1: usize,
     l2: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     update_peak_memory(start_memory, &mut peak_memory);
 
-    // Ensure p is at least 2 (we need at least 2 partitions)
-    let p = p.max(2);
+    let r = h.rows;
+    let mut columns: Vec<usize> = (0..n).collect();
 
-    // Calculate weights for each partition
-    let weights: Vec<usize> = if p == 2 {
-        // For p=2 case (similar to ball-collision)
-        vec![weight / 2, weight - weight / 2]
-    } else {
-        // For p > 2 case, try to distribute weight evenly
-        let base_weight = weight / p;
-        let remainder = weight % p;
-        (0..p)
-            .map(|i| {
-                if i < remainder {
-                    base_weight + 1
-                } else {
-                    base_weight
-                }
-            })
-            .collect()
-    };
-
-    // Create p partitions of roughly equal size
-    let partition_size = n / p;
-    let mut partitions: Vec<Vec<usize>> = Vec::with_capacity(p);
-    for i in 0..p {
-        let start_idx = i * partition_size;
-        let end_idx = if i == p - 1 {
-            n
-        } else {
-            (i + 1) * partition_size
+    // e2 = e_a + e_b with wt(e_a) = wt(e_b) = p/2, so p has to be even
+    if p % 2 == 1 {
+        let metrics = AlgorithmMetrics {
+            time: start_time.elapsed().as_micros() as usize,
+            peak_memory,
+            iterations: 0,
+            success_probability: None,
         };
-        partitions.push((start_idx..end_idx).collect());
+        let message = format!("MMT needs an even p, got p = {}", p);
+        return (Err(PqcatError::InvalidParameters(message)), metrics);
     }
+    let p = p.min(weight & !1);
+    let p_half = p / 2;
+    // For odd p/2, e_a leans left and e_b leans right so that e2 still has p/2 errors per half
+    let (heavy, light) = (p_half.div_ceil(2), p_half / 2);
 
-    let mut rng = rng();
-    let r = h.shape()[0]; // Number of rows in H (syndrome length)
-    let syndrome_vec: Vec<u8> = syndrome.iter().copied().collect();
-
-    // ===== PHASE 1: Generate lists L1 and L2 =====
-
-    // Generate list L1 (combinations from first p/2 partitions)
-    let mut l1_map: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-    for _ in 0..l1 {
-        // Select indices from first half of partitions with appropriate weights
-        let mut subset: Vec<usize> = Vec::new();
-        for i in 0..(p / 2) {
-            let partition = &partitions[i];
-            let weight_i = weights[i];
-            if weight_i > 0 && !partition.is_empty() {
-                subset.extend(
-                    partition
-                        .choose_multiple(&mut rng, weight_i.min(partition.len()))
-                        .cloned(),
-                );
-            }
-        }
+    // The window (l1 + l2 bits) must fit into the syndrome and into one word
+    let l = (l1 + l2).min(r).min(64);
+    let l1 = l1.min(l);
+    let l2 = l - l1;
 
-        // Calculate partial syndrome
-        let mut partial_syndrome = vec![0; r];
-        for &idx in &subset {
-            for j in 0..r {
-                partial_syndrome[j] ^= h[[j, idx]];
-            }
-        }
+    let mut loop_count = 0;
+    let mut reduced_rows = r - l;
 
-        // Store in map
-        l1_map.entry(partial_syndrome).or_default().push(subset);
-    }
+    while loop_count < MAX_ITERATIONS {
+        loop_count += 1;
 
-    // Generate list L2 (combinations from second p/2 partitions)
-    let mut l2_map: HashMap<Vec<u8>, Vec<Vec<usize>>> = HashMap::new();
-    for _ in 0..l2 {
-        // Select indices from second half of partitions with appropriate weights
-        let mut subset: Vec<usize> = Vec::new();
-        for i in (p / 2)..p {
-            let partition = &partitions[i];
-            let weight_i = weights[i];
-            if weight_i > 0 && !partition.is_empty() {
-                subset.extend(
-                    partition
-                        .choose_multiple(&mut rng, weight_i.min(partition.len()))
-                        .cloned(),
-                );
-            }
-        }
+        // Partial Gaussian elimination leaving an l x (k+l) block H2 on the non-pivot columns
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut s = syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut s), &columns, r - l);
+        reduced_rows = pivots.len();
+        let window = (r - reduced_rows).min(64);
 
-        // Calculate partial syndrome
-        let mut partial_syndrome = vec![0; r];
-        for &idx in &subset {
-            for j in 0..r {
-                partial_syndrome[j] ^= h[[j, idx]];
-            }
+        let mut is_pivot = vec![false; n];
+        for &col in &pivots {
+            is_pivot[col] = true;
         }
+        let mut window_columns: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+        window_columns.shuffle(rng);
+        let (left_half, right_half) = window_columns.split_at(window_columns.len() / 2);
 
-        // Store in map
-        l2_map.entry(partial_syndrome).or_default().push(subset);
-    }
+        let h_columns = h_reduced.transpose();
+        let column_window: Vec<u64> = (0..n)
+            .map(|j| h_columns.row_vector(j).window(reduced_rows, window))
+            .collect();
+        let target = s.window(reduced_rows, window);
 
-    // ===== PHASE 2: Search for a match =====
+        // ===== Base lists: weight p/4 on each half (L1, L2 for e_a and L3, L4 for e_b) =====
 
-    // For each syndrome in L1, look for matching syndrome in L2
-    for (s1, subsets1) in &l1_map {
-        // Calculate the complementary syndrome
-        let mut target_s2 = syndrome_vec.clone();
-        for i in 0..r {
-            target_s2[i] ^= s1[i];
+        let base_list = |half: &[usize], w: usize| -> Vec<(u64, Vec<usize>)> {
+            generate_subsets(half, w)
+                .map(|subset| {
+                    let key = subset.iter().fold(0, |acc, &j| acc ^ column_window[j]);
+                    (key, subset)
+                })
+                .collect()
+        };
+        let list_1 = base_list(left_half, heavy);
+        let list_2 = base_list(right_half, light);
+        let swapped =
+            (heavy != light).then(|| (base_list(left_half, light), base_list(right_half, heavy)));
+        let (list_3, list_4) = swapped
+            .as_ref()
+            .map_or((&list_1, &list_2), |(l3, l4)| (l3, l4));
+
+        // ===== First merge on l1 bits: L12 hits a random target t, L34 hits s + t =====
+
+        let t = low_bits(rng.random::<u64>(), l1);
+        let list_12 = merge_on_target(&list_1, &list_2, l1, t);
+        let list_34 = merge_on_target(list_3, list_4, l1, low_bits(target, l1) ^ t);
+        update_peak_memory(start_memory, &mut peak_memory);
+
+        // ===== Second merge on the remaining l2 bits =====
+
+        let mut index_34: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (idx, (key, _)) in list_34.iter().enumerate() {
+            index_34
+                .entry(low_bits(key >> l1, l2))
+                .or_default()
+                .push(idx);
         }
 
-        // Check if we have the complementary syndrome in L2
-        if let Some(subsets2) = l2_map.get(&target_s2) {
-            // Try combinations
-            for subset1 in subsets1 {
-                for subset2 in subsets2 {
-                    // Combine subsets to form error vector
-                    let mut candidate_error = vec![0; n];
-                    for &idx in subset1.iter().chain(subset2.iter()) {
-                        candidate_error[idx] = 1;
-                    }
-
-                    // Verify weight and syndrome
-                    let actual_weight = candidate_error.iter().filter(|&&bit| bit == 1).count();
-                    if actual_weight == weight {
-                        // Calculate full syndrome to verify
-                        let mut check_syndrome = vec![0; r];
-                        for idx in 0..n {
-                            if candidate_error[idx] == 1 {
-                                for j in 0..r {
-                                    check_syndrome[j] ^= h[[j, idx]];
-                                }
-                            }
-                        }
-
-                        if check_syndrome == syndrome_vec {
-                            update_peak_memory(start_memory, &mut peak_memory);
-
-                            let metrics = AlgorithmMetrics {
-                                time: start_time.elapsed().as_micros() as usize,
-                                peak_memory,
-                            };
-
-                            return (Some(candidate_error), metrics);
-                        }
-                    }
+        // Each e2 has several representations e_a + e_b, only keep the first one we see
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+
+        for (key_a, support_a) in &list_12 {
+            let needed = low_bits((key_a ^ target) >> l1, l2);
+            let Some(matches) = index_34.get(&needed) else {
+                continue;
+            };
+
+            for &idx in matches {
+                let support_b = &list_34[idx].1;
+
+                // Overlapping positions cancel (1 + 1 = 0); only weight exactly p is a valid e2
+                let mut e2 = BinaryVector::from_support(n, support_a);
+                e2.xor_assign(&BinaryVector::from_support(n, support_b));
+                if e2.weight() != p {
+                    continue;
+                }
+                let e2_support = e2.support();
+                if !seen.insert(e2_support.clone()) {
+                    continue;
+                }
+
+                // e1 = s1 + H1·e2 sits on the identity part and must have weight w - p
+                let residual = s.xor(&calculate_partial_syndrome(&h_columns, &e2_support));
+                if !residual.slice(reduced_rows..r).is_zero() || residual.weight() > weight - p {
+                    continue;
                 }
+
+                let mut candidate_error = e2.to_bits();
+                for (row, &col) in pivots.iter().enumerate() {
+                    candidate_error[col] = residual.get(row);
+                }
+
+                update_peak_memory(start_memory, &mut peak_memory);
+
+                let metrics = AlgorithmMetrics {
+                    time: start_time.elapsed().as_micros() as usize,
+                    peak_memory,
+                    iterations: loop_count,
+                    success_probability: Some(success_probability(
+                        n,
+                        n - r,
+                        weight,
+                        p,
+                        l1,
+                        r - reduced_rows - l1,
+                    )),
+                };
+
+                return (Ok(candidate_error), metrics);
             }
         }
     }
@@ -174,7 +230,76 @@ pub fn run_mmt_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(
+            n,
+            n - r,
+            weight,
+            p,
+            l1,
+            r - reduced_rows - l1,
+        )),
+    };
+
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+// MMT works in syndrome space, so a received vector is only used for its syndrome
+impl Decoder for Mmt {
+    fn name(&self) -> &'static str {
+        "mmt"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        let syndrome = input.syndrome();
+        DecodeOutcome::from_run(run_mmt_algorithm(
+            input.h,
+            &syndrome,
+            input.h.cols,
+            input.weight,
+            self.p,
+            self.l1,
+            self.l2,
+            rng,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
     };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
 
-    (None, metrics)
+    #[test]
+    fn decodes_a_planted_error() {
+        // The registry default: a random [31, 15] code with four errors
+        let (n, k, w) = (31, 15, 4);
+        let mut rng = StdRng::seed_from_u64(5);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
+
+        let decoded = Mmt { p: 2, l1: 1, l2: 2 }
+            .decode(&input, &mut rng)
+            .result
+            .unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
 }
@@ -1,10 +1,16 @@
 pub mod algorithm_utils;
 pub mod ball_collision;
+pub mod bit_flipping;
 pub mod bjmm;
 pub mod config;
+pub mod decoder;
+pub mod dumer;
 pub mod lee_brickell;
 pub mod metrics;
 pub mod mmt;
 pub mod patterson;
 pub mod prange;
+pub mod reaction_attack;
+pub mod registry;
 pub mod stern;
+pub mod sugiyama;
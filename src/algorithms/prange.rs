@@ -1,53 +1,71 @@
 use crate::algorithms::algorithm_utils::calculate_syndrome;
 use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::Array2;
-use rand::rng;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, DecodeInput, DecodeOutcome, PqcatError, Prange};
+use rand::Rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use std::time::Instant;
 
+/// Probability that one random information set contains none of the w error positions,
+/// i.e. C(n-k, w) / C(n, w)
+pub fn success_probability(n: usize, k: usize, w: usize) -> f64 {
+    if w > n - k {
+        return 0.0;
+    }
+    (0..w)
+        .map(|i| (n - k - i) as f64 / (n - i) as f64)
+        .product()
+}
+
 pub fn run_prange_algorithm(
     received_vector: &[u8],
-    h: &Array2<u8>,
+    h: &BinaryMatrix,
     weight: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     let target_syndrome = calculate_syndrome(received_vector, h);
     update_peak_memory(start_memory, &mut peak_memory);
-    let n = h.shape()[1]; // Length of the error vector
-    let mut indices: Vec<usize> = (0..n).collect(); // All possible indices
+    let (r, n) = h.dim();
+    let mut columns: Vec<usize> = (0..n).collect(); // Column order, reshuffled every iteration
 
     let mut loop_count = 0;
+    let mut rank = r;
 
     while loop_count < MAX_ITERATIONS {
-        // Shuffle and take the first `weight` indices as candidate positions for the error vector
-        indices.shuffle(&mut rng());
-        let chosen_indices = &indices[..weight];
-
-        // Create a candidate error vector
-        let mut candidate_error = vec![0; n];
-        for &i in chosen_indices {
-            candidate_error[i] = 1;
-        }
+        loop_count += 1;
 
-        // Calculate the candidate syndrome: S = H * E^T
-        let candidate_syndrome = calculate_syndrome(&candidate_error, h);
+        // Random column permutation, then Gaussian elimination over the first independent n-k columns
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut syndrome = target_syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut syndrome), &columns, r);
+        rank = pivots.len();
+
+        // If no error lies in the information set, the transformed syndrome is the error itself
+        if syndrome.weight() <= weight {
+            let mut candidate_error = vec![0; n];
+            for (row, &col) in pivots.iter().enumerate() {
+                candidate_error[col] = syndrome.get(row);
+            }
 
-        // If the syndrome matches (i.e., it is zero), we found a valid error vector
-        if candidate_syndrome == target_syndrome {
             update_peak_memory(start_memory, &mut peak_memory);
 
             let metrics = AlgorithmMetrics {
                 time: start_time.elapsed().as_micros() as usize,
                 peak_memory,
+                iterations: loop_count,
+                success_probability: Some(success_probability(n, n - rank, weight)),
             };
 
-            return (Some(candidate_error), metrics);
+            return (Ok(candidate_error), metrics);
         }
-        loop_count += 1;
     }
 
     update_peak_memory(start_memory, &mut peak_memory);
@@ -55,7 +73,57 @@ pub fn run_prange_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(n, n - rank, weight)),
+    };
+
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for Prange {
+    fn name(&self) -> &'static str {
+        "prange"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_prange_algorithm(received, input.h, input.weight, rng)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
     };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
 
-    (None, metrics)
+    #[test]
+    fn decodes_a_planted_error() {
+        // A random [23, 12] code, three errors on a random codeword
+        let (n, k, w) = (23, 12, 3);
+        let mut rng = StdRng::seed_from_u64(1);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
+
+        let decoded = Prange.decode(&input, &mut rng).result.unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
 }
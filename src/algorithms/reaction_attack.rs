@@ -0,0 +1,390 @@
+use crate::algorithms::algorithm_utils::seeded_rng;
+use crate::algorithms::bit_flipping::{ParityChecks, flip_until_decoded};
+use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
+use crate::types::{
+    BinaryMatrix, BinaryVector, Circulant, FlipRule, PqcatError, QcMdpcKey, ReactionAttackReport,
+};
+use rand::Rng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+const MAX_SEARCH_NODES: usize = 1_000_000; // Budget of each backtracking search for h0
+const SPECTRUM_WIDENING_STEPS: usize = 16; // Rounds in which the rejected distances are let in
+
+/// Local stand-in for the decryption oracle of a QC-MDPC scheme: it decodes with the private key
+/// and only reveals whether decoding worked, which is all a reaction attack gets to see
+pub struct DecryptionOracle {
+    key: QcMdpcKey,
+    checks: ParityChecks,
+    rule: FlipRule,
+    t: usize,
+    queries: AtomicUsize,
+}
+
+impl DecryptionOracle {
+    pub fn new(key: QcMdpcKey, rule: FlipRule, t: usize) -> Self {
+        let checks = ParityChecks::from_key(&key);
+        DecryptionOracle {
+            key,
+            checks,
+            rule,
+            t,
+            queries: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn queries(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// Decrypt a ciphertext c = mG + e; a failure is anything but an error of weight t with zero syndrome
+    pub fn decrypt(&self, ciphertext: &[u8], rng: &mut impl Rng) -> bool {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let mut syndrome = self.checks.syndrome(ciphertext);
+        let mut error = vec![0; ciphertext.len()];
+        flip_until_decoded(
+            self.rule,
+            &self.checks,
+            &self.key,
+            self.t,
+            &mut error,
+            &mut syndrome,
+            rng,
+        );
+        syndrome.iter().all(|&bit| bit == 0)
+            && error.iter().filter(|&&bit| bit == 1).count() == self.t
+    }
+}
+
+fn cyclic_distance(i: usize, j: usize, r: usize) -> usize {
+    let diff = i.abs_diff(j);
+    diff.min(r - diff)
+}
+
+/// Distance spectrum of a circulant: entry delta counts the pairs of its support at cyclic distance delta
+pub fn distance_spectrum(circulant: &Circulant) -> Vec<usize> {
+    let r = circulant.r();
+    let support = circulant.support();
+    let mut spectrum = vec![0; r / 2 + 1];
+    for (a, &i) in support.iter().enumerate() {
+        for &j in &support[a + 1..] {
+            spectrum[cyclic_distance(i, j, r)] += 1;
+        }
+    }
+    spectrum
+}
+
+/// The public key P = h1 h0^-1, read off the first row of G = [P^T | I]
+fn public_key(g: &BinaryMatrix, r: usize) -> Circulant {
+    Circulant::from_dense(g.row_vector(0).slice(0..r)).transpose()
+}
+
+/// Error of weight t in the first block made of t/2 pairs of bits at distance delta (plus one
+/// random bit for odd t), so that it only favours that one distance
+fn pair_error(r: usize, n: usize, t: usize, delta: usize, rng: &mut impl Rng) -> Vec<u8> {
+    let mut error = vec![0; n];
+    let mut placed = 0;
+    while placed + 2 <= t {
+        let i = rng.random_range(0..r);
+        let j = (i + delta) % r;
+        if error[i] == 0 && error[j] == 0 {
+            error[i] = 1;
+            error[j] = 1;
+            placed += 2;
+        }
+    }
+    while placed < t {
+        let i = rng.random_range(0..r);
+        if error[i] == 0 {
+            error[i] = 1;
+            placed += 1;
+        }
+    }
+    error
+}
+
+/// Cut between the distances of h0 and the others. h0 has at most `max_distances` distinct
+/// distances, so they make up one end of the sorted rates, and the cut is the widest gap between
+/// consecutive rates within `max_distances` of either end. Returns the threshold and whether the
+/// spectrum lies above it
+fn split_threshold(rates: &[f64], max_distances: usize) -> (f64, bool) {
+    let mut sorted = rates.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let end = max_distances.min(sorted.len() - 1);
+
+    let (mut widest, mut threshold, mut spectrum_above) = (f64::NEG_INFINITY, sorted[0], true);
+    for kept in 1..=end {
+        let (below, above) = (sorted[sorted.len() - kept - 1], sorted[sorted.len() - kept]);
+        if above - below > widest {
+            (widest, threshold, spectrum_above) = (above - below, (above + below) / 2.0, true);
+        }
+        let (below, above) = (sorted[kept - 1], sorted[kept]);
+        if above - below > widest {
+            (widest, threshold, spectrum_above) = (above - below, (above + below) / 2.0, false);
+        }
+    }
+    (threshold, spectrum_above)
+}
+
+/// Find a weight-d circulant whose pairwise distances all lie in the spectrum and for which
+/// h1 = h0 P is sparse too. Rotating h0 rotates h1 along, so the first position is fixed to 0
+fn rebuild_h0(
+    in_spectrum: &[bool],
+    r: usize,
+    d: usize,
+    public_key: &Circulant,
+) -> Option<Circulant> {
+    let candidates: Vec<usize> = (1..r)
+        .filter(|&p| in_spectrum[cyclic_distance(0, p, r)])
+        .collect();
+    let mut positions = vec![0];
+    let mut nodes = 0;
+    extend(
+        &mut positions,
+        &candidates,
+        in_spectrum,
+        r,
+        d,
+        public_key,
+        &mut nodes,
+    )
+}
+
+fn extend(
+    positions: &mut Vec<usize>,
+    candidates: &[usize],
+    in_spectrum: &[bool],
+    r: usize,
+    d: usize,
+    public_key: &Circulant,
+    nodes: &mut usize,
+) -> Option<Circulant> {
+    *nodes += 1;
+    if *nodes > MAX_SEARCH_NODES {
+        return None;
+    }
+    if positions.len() == d {
+        let h0 = Circulant::from_support(r, positions.clone());
+        return (h0.mul(public_key).weight() == d).then_some(h0);
+    }
+    if positions.len() + candidates.len() < d {
+        return None;
+    }
+
+    for (a, &p) in candidates.iter().enumerate() {
+        if positions.len() + candidates.len() - a < d {
+            break;
+        }
+        let remaining: Vec<usize> = candidates[a + 1..]
+            .iter()
+            .copied()
+            .filter(|&q| in_spectrum[cyclic_distance(p, q, r)])
+            .collect();
+        positions.push(p);
+        let found = extend(positions, &remaining, in_spectrum, r, d, public_key, nodes);
+        positions.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// Guo–Johansson–Stankovski reaction attack on a two-block QC-MDPC key. For every distance delta
+/// the oracle decrypts `queries_per_distance` ciphertexts whose errors are built from pairs at
+/// distance delta; distances that occur in h0 shift the failure rate measurably, which reveals the
+/// distance spectrum of h0, and h0 itself is then rebuilt from it with the public key
+pub fn run_reaction_attack(
+    g: &BinaryMatrix,
+    key: &QcMdpcKey,
+    rule: FlipRule,
+    t: usize,
+    queries_per_distance: usize,
+    rng: &mut impl Rng,
+) -> Result<(ReactionAttackReport, AlgorithmMetrics), PqcatError> {
+    let r = key.r;
+    if key.blocks.len() != 2 {
+        return Err(PqcatError::Unsupported(format!(
+            "The reaction attack targets keys with 2 circulant blocks, got {}",
+            key.blocks.len()
+        )));
+    }
+    // Pairs at distance delta are edges of the cycles i -> i + delta through Z_r. Before the last
+    // pair at most t - 2 < r / 2 nodes are taken, so more than half of the nodes are free and some
+    // cycle has two consecutive free nodes: up to r / 2 errors the pairs can always be placed
+    if t < 2 || t > r / 2 {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid error weight for the reaction attack: t ({}) must be in 2..={}",
+            t,
+            r / 2
+        )));
+    }
+
+    let start_time = Instant::now();
+    let start_memory = start_memory_tracking();
+    let mut peak_memory = 0;
+
+    let n = 2 * r;
+    let generator_columns = g.transpose();
+    let oracle = DecryptionOracle::new(key.clone(), rule, t);
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    // Phase 1: failure statistics by distance, with the distances spread over every available core.
+    // Each distance queries with its own generator, so the statistics do not depend on the split
+    let workers = thread::available_parallelism().map_or(1, |p| p.get());
+    let distances: Vec<(usize, u64)> = (1..=r / 2).map(|delta| (delta, rng.random())).collect();
+    let chunk = distances.len().div_ceil(workers).max(1);
+    let failure_counts: Vec<usize> = thread::scope(|scope| {
+        let handles: Vec<_> = distances
+            .chunks(chunk)
+            .map(|share| {
+                let (oracle, generator_columns) = (&oracle, &generator_columns);
+                scope.spawn(move || {
+                    share
+                        .iter()
+                        .map(|&(delta, seed)| {
+                            let mut rng = seeded_rng(seed);
+                            (0..queries_per_distance)
+                                .filter(|_| {
+                                    let message: Vec<u8> =
+                                        (0..g.rows).map(|_| rng.random_range(0..=1)).collect();
+                                    let mut ciphertext = generator_columns
+                                        .mul_vector(&BinaryVector::from_bits(&message))
+                                        .to_bits();
+                                    let error = pair_error(r, n, t, delta, &mut rng);
+                                    for (c, e) in ciphertext.iter_mut().zip(error) {
+                                        *c ^= e;
+                                    }
+                                    !oracle.decrypt(&ciphertext, &mut rng)
+                                })
+                                .count()
+                        })
+                        .collect::<Vec<usize>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Reaction attack worker panicked"))
+            .collect()
+    });
+    let failures = failure_counts.iter().sum();
+    let failure_rates: Vec<f64> = failure_counts
+        .iter()
+        .map(|&failed| failed as f64 / queries_per_distance.max(1) as f64)
+        .collect();
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    // Phase 2: split the distances in two groups by failure rate. Whether pairs at a distance of
+    // h0 make decoding easier or harder depends on the decoder and on t, so the spectrum is looked
+    // for at both ends of the rates
+    let d = key.circulant_weight;
+    let (threshold, spectrum_above) = split_threshold(&failure_rates, d * (d - 1) / 2);
+    let in_spectrum_score = |rate: f64| {
+        if spectrum_above {
+            rate - threshold
+        } else {
+            threshold - rate
+        }
+    };
+    let spectrum: Vec<usize> = (1..)
+        .zip(&failure_rates)
+        .filter(|&(_, &rate)| in_spectrum_score(rate) > 0.0)
+        .map(|(delta, _)| delta)
+        .collect();
+    let true_spectrum = distance_spectrum(&key.blocks[0]);
+    let spectrum_errors = (1..=r / 2)
+        .filter(|delta| spectrum.contains(delta) != (true_spectrum[*delta] > 0))
+        .count();
+
+    // Phase 3: rebuild h0 from the spectrum. A distance of h0 classified as absent rules the real
+    // key out, so when no candidate passes the public key check the closest distances below the
+    // threshold are let in as well
+    let mut ranked: Vec<usize> = (1..=r / 2).collect();
+    ranked.sort_by(|&a, &b| {
+        in_spectrum_score(failure_rates[b - 1]).total_cmp(&in_spectrum_score(failure_rates[a - 1]))
+    });
+    let public_key = public_key(g, r);
+    let step = (ranked.len() / SPECTRUM_WIDENING_STEPS).max(1);
+    let mut recovered_h0 = None;
+    let mut included = spectrum.len();
+    while recovered_h0.is_none() && included <= ranked.len() {
+        let mut in_spectrum = vec![false; r / 2 + 1];
+        for &delta in &ranked[..included] {
+            in_spectrum[delta] = true;
+        }
+        recovered_h0 = rebuild_h0(&in_spectrum, r, d, &public_key);
+        if included == ranked.len() {
+            break;
+        }
+        included = (included + step).min(ranked.len());
+    }
+
+    // Checked against the real key up to rotation
+    let success = recovered_h0.as_ref().is_some_and(|h0| {
+        let real = &key.blocks[0];
+        real.support()
+            .iter()
+            .any(|&shift| h0.rotate(shift) == *real)
+    });
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    let report = ReactionAttackReport {
+        queries: oracle.queries(),
+        failures,
+        failure_rates,
+        spectrum,
+        spectrum_errors,
+        recovered_h0,
+        success,
+    };
+    let metrics = AlgorithmMetrics {
+        time: start_time.elapsed().as_micros() as usize,
+        peak_memory,
+        iterations: report.queries,
+        success_probability: None,
+    };
+
+    Ok((report, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_generator::generate_qc_code;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn split_threshold_finds_the_spectrum_at_either_end() {
+        let mut rates = vec![0.40, 0.42, 0.45, 0.47, 0.50, 0.52, 0.55];
+        rates.extend([0.90, 0.92]);
+        let (threshold, above) = split_threshold(&rates, 3);
+        assert!(above && threshold > 0.55 && threshold < 0.90);
+
+        let rates: Vec<f64> = rates.iter().map(|rate| 1.0 - rate).collect();
+        let (threshold, above) = split_threshold(&rates, 3);
+        assert!(!above && threshold > 0.10 && threshold < 0.45);
+    }
+
+    #[test]
+    fn recovers_h0_of_a_small_key() {
+        // Two circulant blocks of size 131 and weight 5; ten errors fail about 60% of the time
+        let mut rng = StdRng::seed_from_u64(3);
+        let (g, _h, key) = generate_qc_code(262, 131, 5, &mut rng).unwrap();
+
+        let (report, _) =
+            run_reaction_attack(&g, &key, FlipRule::BlackGrayFlip, 10, 200, &mut rng).unwrap();
+        assert_eq!(report.spectrum_errors, 0);
+        assert_eq!(report.queries, 65 * 200);
+
+        let real = &key.blocks[0];
+        let h0 = report.recovered_h0.unwrap();
+        assert!(
+            real.support()
+                .iter()
+                .any(|&shift| h0.rotate(shift) == *real)
+        );
+    }
+}
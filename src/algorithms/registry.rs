@@ -0,0 +1,334 @@
+use crate::algorithms::decoder::Decoder;
+use crate::types::{
+    AlgorithmSpec, BallCollision, BitFlipping, Bjmm, Dumer, FlipRule, LeeBrickell, Mmt, ParamSpec,
+    PartitionParams, Patterson, PqcatError, Prange, RootFinder, Stern, Sugiyama,
+};
+
+// Information-set decoders only need H, so they run on every code the generator knows
+const ANY_CODE: &[&str] = &[
+    "random",
+    "hamming",
+    "goppa",
+    "goppa_squarefree",
+    "goppa_mceliece",
+    "qc",
+];
+const HAMMING_FIRST: &[&str] = &[
+    "hamming",
+    "random",
+    "goppa",
+    "goppa_squarefree",
+    "goppa_mceliece",
+    "qc",
+];
+const GOPPA: &[&str] = &["goppa", "goppa_mceliece"];
+const GOPPA_ANY: &[&str] = &["goppa", "goppa_squarefree", "goppa_mceliece"];
+const QC: &[&str] = &["qc"];
+
+const COLLISION_PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "p",
+        help: "Error weight in each half of the information set",
+        default: 1,
+        range: 1..=16,
+    },
+    ParamSpec {
+        name: "l",
+        help: "Size of the collision window (syndrome bits)",
+        default: 2,
+        range: 0..=64,
+    },
+];
+
+// Dumer splits the whole (k+l)-sized window rather than the information set
+const DUMER_PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "p",
+        help: "Error weight in each half of the (k+l)-sized window",
+        default: 1,
+        range: 1..=16,
+    },
+    ParamSpec {
+        name: "l",
+        help: "Size of the collision window (syndrome bits)",
+        default: 2,
+        range: 0..=64,
+    },
+];
+
+const FLIP_DEFAULT_CODE: (usize, usize, usize) = (1174, 587, 10);
+
+fn bit_flipping(rule: FlipRule) -> Box<dyn Decoder> {
+    Box::new(BitFlipping { rule })
+}
+
+// Every parameter is set when `build` runs, see resolve_params
+fn param(params: &PartitionParams, name: &str) -> usize {
+    params.get(name).unwrap_or(0)
+}
+
+pub const ALGORITHMS: &[AlgorithmSpec] = &[
+    AlgorithmSpec {
+        name: "prange",
+        about: "Prange's information-set decoding",
+        default_code: (15, 11, 1),
+        code_types: HAMMING_FIRST,
+        params: &[],
+        build: |_| Box::new(Prange),
+    },
+    AlgorithmSpec {
+        name: "stern",
+        about: "Stern's algorithm with a collision window",
+        default_code: (15, 11, 1),
+        code_types: HAMMING_FIRST,
+        params: COLLISION_PARAMS,
+        build: |params| {
+            Box::new(Stern {
+                p: param(params, "p"),
+                l: param(params, "l"),
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "dumer",
+        about: "Dumer's algorithm, matching l syndrome bits by sorting",
+        default_code: (23, 12, 3),
+        code_types: ANY_CODE,
+        params: DUMER_PARAMS,
+        build: |params| {
+            Box::new(Dumer {
+                p: param(params, "p"),
+                l: param(params, "l"),
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "lee_brickell",
+        about: "Lee-Brickell, guessing p errors inside the information set",
+        default_code: (23, 12, 3),
+        code_types: ANY_CODE,
+        params: &[ParamSpec {
+            name: "p",
+            help: "Number of errors guessed inside the information set",
+            default: 2,
+            range: 0..=16,
+        }],
+        build: |params| {
+            Box::new(LeeBrickell {
+                p: param(params, "p"),
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "ball_collision",
+        about: "Ball-collision decoding",
+        default_code: (23, 12, 3),
+        code_types: ANY_CODE,
+        params: &[],
+        build: |_| Box::new(BallCollision),
+    },
+    AlgorithmSpec {
+        name: "mmt",
+        about: "May-Meurer-Thomae with a two-level merge tree",
+        default_code: (31, 15, 4),
+        code_types: ANY_CODE,
+        params: &[
+            ParamSpec {
+                name: "p",
+                help: "Error weight in the (k+l)-sized window, must be even",
+                default: 2,
+                range: 0..=32,
+            },
+            ParamSpec {
+                name: "l1",
+                help: "Bits fixed by the first merge",
+                default: 1,
+                range: 0..=64,
+            },
+            ParamSpec {
+                name: "l2",
+                help: "Bits fixed by the second merge",
+                default: 2,
+                range: 0..=64,
+            },
+        ],
+        build: |params| {
+            Box::new(Mmt {
+                p: param(params, "p"),
+                l1: param(params, "l1"),
+                l2: param(params, "l2"),
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "bjmm",
+        about: "Becker-Joux-May-Meurer with a depth-3 tree and overlapping representations",
+        default_code: (23, 12, 3),
+        code_types: ANY_CODE,
+        params: &[
+            ParamSpec {
+                name: "p",
+                help: "Error weight in the (k+l)-sized window",
+                default: 2,
+                range: 0..=32,
+            },
+            ParamSpec {
+                name: "eps1",
+                help: "Overlapping positions on level 1",
+                default: 1,
+                range: 0..=16,
+            },
+            ParamSpec {
+                name: "eps2",
+                help: "Overlapping positions on level 2",
+                default: 0,
+                range: 0..=16,
+            },
+            ParamSpec {
+                name: "l1",
+                help: "Bits fixed on level 1; the top merge covers all l1 + l2 bits",
+                default: 2,
+                range: 0..=64,
+            },
+            ParamSpec {
+                name: "l2",
+                help: "Bits fixed on level 2, at most l1",
+                default: 1,
+                range: 0..=64,
+            },
+        ],
+        build: |params| {
+            Box::new(Bjmm {
+                p: param(params, "p"),
+                eps1: param(params, "eps1"),
+                eps2: param(params, "eps2"),
+                l1: param(params, "l1"),
+                l2: param(params, "l2"),
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "patterson",
+        about: "Patterson's algebraic decoder for binary Goppa codes",
+        default_code: (31, 16, 3),
+        code_types: GOPPA,
+        params: &[],
+        build: |_| {
+            Box::new(Patterson {
+                root_finder: RootFinder::AdditiveFft,
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "patterson_trace",
+        about: "Patterson's decoder, locating errors with Berlekamp's trace algorithm",
+        default_code: (31, 16, 3),
+        code_types: GOPPA,
+        params: &[],
+        build: |_| {
+            Box::new(Patterson {
+                root_finder: RootFinder::BerlekampTrace,
+            })
+        },
+    },
+    AlgorithmSpec {
+        name: "sugiyama",
+        about: "Sugiyama's alternant decoder, correcting t errors with g^2",
+        default_code: (30, 15, 3), // Leaves a field element for the root of a reducible g
+        code_types: GOPPA_ANY,
+        params: &[],
+        build: |_| Box::new(Sugiyama),
+    },
+    AlgorithmSpec {
+        name: "gallager",
+        about: "Gallager's bit-flipping decoder for QC-MDPC codes",
+        default_code: FLIP_DEFAULT_CODE,
+        code_types: QC,
+        params: &[],
+        build: |_| bit_flipping(FlipRule::Gallager),
+    },
+    AlgorithmSpec {
+        name: "bgf",
+        about: "BIKE's Black-Gray-Flip decoder for QC-MDPC codes",
+        default_code: FLIP_DEFAULT_CODE,
+        code_types: QC,
+        params: &[],
+        build: |_| bit_flipping(FlipRule::BlackGrayFlip),
+    },
+    AlgorithmSpec {
+        name: "step_by_step",
+        about: "Step-by-step bit-flipping decoder for QC-MDPC codes",
+        default_code: FLIP_DEFAULT_CODE,
+        code_types: QC,
+        params: &[],
+        build: |_| bit_flipping(FlipRule::StepByStep),
+    },
+];
+
+/// Registry entry of `name`, accepting the CLI spelling with dashes too
+pub fn find_algorithm(name: &str) -> Option<&'static AlgorithmSpec> {
+    let name = name.replace('-', "_");
+    ALGORITHMS.iter().find(|spec| spec.name == name)
+}
+
+/// Fill the parameters `spec` declares with their defaults where unset and check their ranges
+pub fn resolve_params(
+    spec: &AlgorithmSpec,
+    params: &PartitionParams,
+) -> Result<PartitionParams, PqcatError> {
+    let mut resolved = PartitionParams::default();
+    for param in spec.params {
+        let value = params.get(param.name).unwrap_or(param.default);
+        if !param.range.contains(&value) {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Invalid {} parameter: {} ({}) must be in {}..={}",
+                spec.name,
+                param.name,
+                value,
+                param.range.start(),
+                param.range.end()
+            )));
+        }
+        resolved.set(param.name, value);
+    }
+    Ok(resolved)
+}
+
+/// Decoder registered under `name`, with its parameters taken from `params` and the registry's
+/// defaults for the ones left unset
+pub fn decoder_by_name(
+    name: &str,
+    params: &PartitionParams,
+) -> Result<Box<dyn Decoder>, PqcatError> {
+    let spec = find_algorithm(name)
+        .ok_or_else(|| PqcatError::Unsupported(format!("Unknown algorithm: {}", name)))?;
+    let params = resolve_params(spec, params)?;
+    Ok((spec.build)(&params))
+}
+
+impl PartitionParams {
+    pub fn get(&self, name: &str) -> Option<usize> {
+        match name {
+            "p" => self.p,
+            "l" => self.l,
+            "l1" => self.l1,
+            "l2" => self.l2,
+            "eps1" => self.eps1,
+            "eps2" => self.eps2,
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: usize) {
+        let field = match name {
+            "p" => &mut self.p,
+            "l" => &mut self.l,
+            "l1" => &mut self.l1,
+            "l2" => &mut self.l2,
+            "eps1" => &mut self.eps1,
+            "eps2" => &mut self.eps2,
+            _ => panic!("{} is not an algorithm parameter", name),
+        };
+        *field = Some(value);
+    }
+}
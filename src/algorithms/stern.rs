@@ -1,83 +1,124 @@
-use crate::algorithms::algorithm_utils::{calculate_syndrome, generate_subsets};
+use crate::algorithms::algorithm_utils::{
+    calculate_partial_syndrome, calculate_syndrome, generate_subsets, log_binomial,
+};
+use crate::algorithms::config::MAX_ITERATIONS;
+use crate::algorithms::decoder::{Decoder, decode_received};
 use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
-use ndarray::Array2;
-use rand::rng;
+use crate::codes::code_utils::row_reduce;
+use crate::types::{BinaryMatrix, DecodeInput, DecodeOutcome, PqcatError, Stern};
+use rand::Rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use std::collections::HashMap;
 use std::time::Instant;
 
+/// Probability that one iteration succeeds: exactly p errors in each half of the information set
+/// and none in the l window positions, i.e. C(k1, p) C(k2, p) C(n-k-l, w-2p) / C(n, w)
+pub fn success_probability(n: usize, k: usize, w: usize, p: usize, l: usize) -> f64 {
+    let (k1, k2) = (k / 2, k - k / 2);
+    if 2 * p > w || l > n - k {
+        return 0.0;
+    }
+    (log_binomial(k1, p) + log_binomial(k2, p) + log_binomial(n - k - l, w - 2 * p)
+        - log_binomial(n, w))
+    .exp()
+}
+
 pub fn run_stern_algorithm(
     received_vector: &[u8],
-    h: &Array2<u8>,
+    h: &BinaryMatrix,
     weight: usize,
-) -> (Option<Vec<u8>>, AlgorithmMetrics) {
+    p: usize,
+    l: usize,
+    rng: &mut impl Rng,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
     let start_time = Instant::now();
     let start_memory = start_memory_tracking();
     let mut peak_memory = 0;
 
     let target_syndrome = calculate_syndrome(received_vector, h);
     update_peak_memory(start_memory, &mut peak_memory);
-    let n = h.shape()[1];
-    let m = n / 2 + (n % 2);
-
-    // Split the indices into two sets
-    let indices: Vec<usize> = (0..n).collect();
-    let mut left_indices = indices[..m].to_vec();
-    let mut right_indices = indices[m..].to_vec();
-
-    // Shuffle to add randomness to bare closer resemblance to the probabilistic nature of Stern's algorithm
-    left_indices.shuffle(&mut rng());
-    right_indices.shuffle(&mut rng());
-
-    // Create hash maps for subsets
-    let mut left_map: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
-    let mut right_map: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
-
-    // Populate the left map
-    let left_weight = weight / 2;
-    for subset in generate_subsets(&left_indices, left_weight) {
-        let mut candidate_error = vec![0; n];
-        for &i in &subset {
-            candidate_error[i] = 1;
-        }
-        let syndrome = calculate_syndrome(&candidate_error, h);
-        left_map.insert(syndrome.clone(), subset);
-    }
+    let (r, n) = h.dim();
+    let mut columns: Vec<usize> = (0..n).collect();
+
+    // Both halves contribute p errors, so 2p can't exceed the target weight
+    let p = p.min(weight / 2);
+
+    let mut loop_count = 0;
+    let mut rank = r;
+    let mut window = l;
+
+    while loop_count < MAX_ITERATIONS {
+        loop_count += 1;
 
-    // Populate the right map
-    let right_weight = weight - left_weight;
-    for subset in generate_subsets(&right_indices, right_weight) {
-        let mut candidate_error = vec![0; n];
-        for &i in &subset {
-            candidate_error[i] = 1;
+        // Random information set: permute the columns and reduce H to the identity on n-k of them
+        columns.shuffle(rng);
+        let mut h_reduced = h.clone();
+        let mut syndrome = target_syndrome.clone();
+        let pivots = row_reduce(&mut h_reduced, Some(&mut syndrome), &columns, r);
+        rank = pivots.len();
+        window = l.min(rank).min(64);
+
+        // Information set (non-pivot columns) split into two random halves X and Y
+        let mut is_pivot = vec![false; n];
+        for &col in &pivots {
+            is_pivot[col] = true;
         }
-        let syndrome = calculate_syndrome(&candidate_error, h);
-        right_map.insert(syndrome.clone(), subset);
-    }
+        let mut info_set: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+        info_set.shuffle(rng);
+        let (left, right) = info_set.split_at(info_set.len() / 2);
 
-    // Find matching syndromes in both maps
-    for (left_syndrome, left_subset) in &left_map {
-        let mut complement_syndrome = target_syndrome.clone();
-        for (i, &val) in left_syndrome.iter().enumerate() {
-            complement_syndrome[i] ^= val;
+        // The window is made of the first l pivot rows, i.e. l redundancy positions assumed error-free
+        let h_columns = h_reduced.transpose();
+        let target_window = syndrome.window(0, window);
+
+        // Index all weight-p subsets of X by their l-bit window
+        let mut left_map: HashMap<u64, Vec<Vec<usize>>> = HashMap::new();
+        for subset in generate_subsets(left, p) {
+            let key = calculate_partial_syndrome(&h_columns, &subset).window(0, window);
+            left_map.entry(key).or_default().push(subset);
         }
-        if let Some(right_subset) = right_map.get(&complement_syndrome) {
-            // Combine the subsets to form the error vector
-            let mut candidate_error = vec![0; n];
-            for &i in left_subset {
-                candidate_error[i] = 1;
-            }
-            for &i in right_subset {
-                candidate_error[i] = 1;
-            }
-            update_peak_memory(start_memory, &mut peak_memory);
+        update_peak_memory(start_memory, &mut peak_memory);
+
+        // Match weight-p subsets of Y on the window, then check the weight of what's left
+        for right_subset in generate_subsets(right, p) {
+            let right_syndrome = calculate_partial_syndrome(&h_columns, &right_subset);
+            let key = right_syndrome.window(0, window) ^ target_window;
 
-            let metrics = AlgorithmMetrics {
-                time: start_time.elapsed().as_micros() as usize,
-                peak_memory,
+            let Some(left_subsets) = left_map.get(&key) else {
+                continue;
             };
 
-            return (Some(candidate_error), metrics);
+            for left_subset in left_subsets {
+                let mut residual = syndrome.xor(&right_syndrome);
+                residual.xor_assign(&calculate_partial_syndrome(&h_columns, left_subset));
+
+                if residual.weight() <= weight - 2 * p {
+                    // The residual syndrome lies on identity columns, so it is the rest of the error
+                    let mut candidate_error = vec![0; n];
+                    for &i in left_subset.iter().chain(right_subset.iter()) {
+                        candidate_error[i] = 1;
+                    }
+                    for (row, &col) in pivots.iter().enumerate() {
+                        candidate_error[col] = residual.get(row);
+                    }
+
+                    let metrics = AlgorithmMetrics {
+                        time: start_time.elapsed().as_micros() as usize,
+                        peak_memory,
+                        iterations: loop_count,
+                        success_probability: Some(success_probability(
+                            n,
+                            n - rank,
+                            weight,
+                            p,
+                            window,
+                        )),
+                    };
+
+                    return (Ok(candidate_error), metrics);
+                }
+            }
         }
     }
 
@@ -86,7 +127,60 @@ pub fn run_stern_algorithm(
     let metrics = AlgorithmMetrics {
         time: start_time.elapsed().as_micros() as usize,
         peak_memory,
+        iterations: loop_count,
+        success_probability: Some(success_probability(n, n - rank, weight, p, window)),
+    };
+
+    let iterations = metrics.iterations;
+    (Err(PqcatError::BudgetExhausted { iterations }), metrics)
+}
+
+impl Decoder for Stern {
+    fn name(&self) -> &'static str {
+        "stern"
+    }
+
+    fn decode(&self, input: &DecodeInput, rng: &mut StdRng) -> DecodeOutcome {
+        decode_received(input, |received| {
+            run_stern_algorithm(received, input.h, input.weight, self.p, self.l, rng)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
     };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_a_planted_error() {
+        // A random [23, 12] code, three errors on a random codeword
+        let (n, k, w) = (23, 12, 3);
+        let mut rng = StdRng::seed_from_u64(2);
+        let (g, h, key) = generate_code(n, k, w, "random".to_string(), None, &mut rng).unwrap();
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
 
-    (None, metrics)
+        let decoded = Stern { p: 1, l: 2 }
+            .decode(&input, &mut rng)
+            .result
+            .unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
 }
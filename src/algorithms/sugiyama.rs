@@ -0,0 +1,173 @@
+use crate::algorithms::algorithm_utils::calculate_syndrome;
+use crate::algorithms::decoder::{Decoder, decode_received, missing_key};
+use crate::algorithms::metrics::{AlgorithmMetrics, start_memory_tracking, update_peak_memory};
+use crate::codes::root_finding::evaluate_everywhere;
+use crate::types::{
+    AlternantParams, BinaryMatrix, DecodeInput, DecodeOutcome, FiniteField, Poly, PqcatError,
+    StructuredKey, Sugiyama,
+};
+use rand::rngs::StdRng;
+use std::time::Instant;
+
+/// Syndrome polynomial S(z) = sum_i S_i z^i with S_i = sum over r_j = 1 of y_j * alpha_j^i, i < r
+fn compute_syndrome_polynomial(received: &[u8], params: &AlternantParams) -> Poly {
+    let field = &params.field;
+    let mut syndrome = vec![0; params.r];
+
+    for ((&bit, &alpha), &y) in received
+        .iter()
+        .zip(&params.support)
+        .zip(&params.multipliers)
+    {
+        if bit == 0 {
+            continue;
+        }
+        let mut term = y;
+        for s in syndrome.iter_mut() {
+            *s ^= term;
+            term = field.field_multiply(term, alpha);
+        }
+    }
+
+    Poly::new(syndrome)
+}
+
+/// Sugiyama's key equation sigma(z) S(z) = omega(z) mod z^r with deg omega < deg sigma <= r/2,
+/// solved by running the extended Euclidean algorithm on (z^r, S) until deg r_i < r/2.
+/// sigma(z) = prod (1 - alpha_j z) over the error positions, so its roots are the inverses
+fn error_locator(syndrome: &Poly, r: usize, field: &FiniteField) -> Option<Poly> {
+    let (mut r0, mut r1) = (Poly::monomial(1, r), syndrome.clone());
+    let (mut t0, mut t1) = (Poly::zero(), Poly::one());
+    while r1.degree().is_some_and(|d| d >= r / 2) {
+        let (q, rem) = r0.divmod(&r1, field).ok()?;
+        let t2 = t0.add(&q.mul(&t1, field));
+        (r0, r1) = (r1, rem);
+        (t0, t1) = (t1, t2);
+    }
+    Some(t1)
+}
+
+pub fn run_sugiyama_algorithm(
+    received_vector: &[u8],
+    h: &BinaryMatrix,
+    alternant_params: &AlternantParams,
+) -> (Result<Vec<u8>, PqcatError>, AlgorithmMetrics) {
+    let start_time = Instant::now();
+    let start_memory = start_memory_tracking();
+    let mut peak_memory = 0;
+
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    let field = &alternant_params.field;
+    let n = received_vector.len();
+
+    let syndrome = compute_syndrome_polynomial(received_vector, alternant_params);
+
+    let error_vector = if syndrome.is_zero() {
+        Some(vec![0; n]) // No errors detected
+    } else {
+        error_locator(&syndrome, alternant_params.r, field).and_then(|sigma| {
+            // Position j is in error iff sigma(1/alpha_j) = 0; a decoding failure shows up as
+            // sigma having fewer roots on the support than its degree
+            let values = evaluate_everywhere(&sigma, field);
+            let positions: Vec<usize> = alternant_params.support[..n]
+                .iter()
+                .enumerate()
+                .filter(|&(_, &alpha)| {
+                    field
+                        .inverse(alpha)
+                        .is_ok_and(|inverse| values[inverse as usize] == 0)
+                })
+                .map(|(j, _)| j)
+                .collect();
+
+            (Some(positions.len()) == sigma.degree()).then(|| {
+                let mut error_vector = vec![0; n];
+                for &pos in &positions {
+                    error_vector[pos] = 1;
+                }
+                error_vector
+            })
+        })
+    };
+
+    // Binary error values are always 1, so no Forney step: just check the result against H
+    let error_vector =
+        error_vector.filter(|e| calculate_syndrome(e, h) == calculate_syndrome(received_vector, h));
+
+    update_peak_memory(start_memory, &mut peak_memory);
+
+    let metrics = AlgorithmMetrics {
+        time: start_time.elapsed().as_micros() as usize,
+        peak_memory,
+        iterations: 1,
+        success_probability: None,
+    };
+
+    let error_vector = error_vector.ok_or_else(|| {
+        PqcatError::Undecodable(
+            "no error vector of weight at most t explains the syndrome".to_string(),
+        )
+    });
+    (error_vector, metrics)
+}
+
+impl Decoder for Sugiyama {
+    fn name(&self) -> &'static str {
+        "sugiyama"
+    }
+
+    fn decode(&self, input: &DecodeInput, _rng: &mut StdRng) -> DecodeOutcome {
+        let Some(StructuredKey::Goppa(goppa_params)) = input.key else {
+            return DecodeOutcome::failed(missing_key(self, "Goppa"));
+        };
+        let alternant_params = match AlternantParams::from_goppa(goppa_params) {
+            Ok(alternant_params) => alternant_params,
+            Err(error) => return DecodeOutcome::failed(error),
+        };
+        decode_received(input, |received| {
+            run_sugiyama_algorithm(received, input.h, &alternant_params)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::algorithm_utils::{
+        apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error,
+    };
+    use crate::code_generator::generate_code;
+    use crate::types::DecodeTarget;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_a_square_free_goppa_code() {
+        // The registry default, with a reducible g so that only the square-free property is used
+        let (n, k, w) = (30, 15, 3);
+        let mut rng = StdRng::seed_from_u64(7);
+        let (g, h, key) =
+            generate_code(n, k, w, "goppa_squarefree".to_string(), None, &mut rng).unwrap();
+        let Some(StructuredKey::Goppa(goppa_params)) = &key else {
+            panic!("Goppa codes come with their key");
+        };
+        assert!(!goppa_params.goppa_poly.is_irreducible(&goppa_params.field));
+
+        let error = generate_random_error_vector(n, w, &mut rng).unwrap();
+        let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+        let input = DecodeInput {
+            h: &h,
+            target: DecodeTarget::Received(&received),
+            weight: w,
+            key: key.as_ref(),
+        };
+
+        let decoded = Sugiyama.decode(&input, &mut rng).result.unwrap();
+        assert!(is_valid_error(
+            &decoded,
+            &calculate_syndrome(&error, &h),
+            &h,
+            w
+        ));
+    }
+}
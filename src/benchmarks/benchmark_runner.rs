@@ -1,17 +1,24 @@
+use crate::algorithms::algorithm_utils::random_seed;
+use crate::algorithms::registry::{ALGORITHMS, find_algorithm};
 use crate::benchmarks::benchmark_utils::{
     calculate_statistics, create_output_files, ensure_results_directory, execute_benchmark_runs,
     print_summary, write_results_to_file,
 };
-use crate::types::BenchmarkConfig;
+use crate::benchmarks::dfr::run_dfr_sweep;
+use crate::types::{BenchmarkConfig, DfrConfig};
 
 #[allow(dead_code)]
 pub fn run_benchmark(config: BenchmarkConfig) {
-    // Setup phase
+    // Setup phase, fixing the seed so the summary records how to replay the benchmark
+    let config = match config.seed {
+        Some(_) => config,
+        None => config.with_seed(random_seed()),
+    };
     ensure_results_directory();
     let (mut writer, txt_filename) = create_output_files(&config);
 
     // Execution phase
-    let results = execute_benchmark_runs(&config);
+    let results = execute_benchmark_runs(&config, &mut writer);
 
     // Analysis and reporting phase
     let stats = calculate_statistics(&results);
@@ -21,17 +28,21 @@ pub fn run_benchmark(config: BenchmarkConfig) {
 
 // ==================== BATCH TEST FUNCTIONS ====================
 
+// Bulk tests of every code family the algorithm supports according to the registry
 #[allow(dead_code)]
 pub fn run_all_tests_for_algorithm(algorithm: &str, runs: usize) {
-    // Skip incompatible algorithm-code combinations
-    match algorithm {
-        "patterson" => run_all_goppa_tests(algorithm, runs),
-        "mmt" => run_all_mmt_tests(runs),
-        _ => {
-            // For all other ISD algorithms
-            run_all_hamming_tests(algorithm, runs);
-            run_all_goppa_tests(algorithm, runs);
-            run_all_qc_tests(algorithm, runs);
+    let Some(spec) = find_algorithm(algorithm) else {
+        eprintln!("Unknown algorithm: {}", algorithm);
+        return;
+    };
+    for code_type in ["hamming", "goppa", "qc"] {
+        if !spec.code_types.contains(&code_type) {
+            continue;
+        }
+        match code_type {
+            "hamming" => run_all_hamming_tests(spec.name, runs),
+            "goppa" => run_all_goppa_tests(spec.name, runs),
+            _ => run_all_qc_tests(spec.name, runs),
         }
     }
 }
@@ -90,106 +101,47 @@ pub fn run_all_qc_tests(algorithm: &str, runs: usize) {
     }
 }
 
-// TODO: untangle MMT-specific tests so that there's less boilerplate
-pub fn run_all_mmt_tests(runs: usize) {
-    run_all_hamming_tests_mmt(runs);
-    run_all_goppa_tests_mmt(runs);
-    run_all_qc_tests_mmt(runs);
-}
-
-pub fn run_all_hamming_tests_mmt(runs: usize) {
-    // Test 1: Scaling code size
-    for i in 0..4 {
-        let config = BenchmarkConfig::hamming_scaling_size(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
-    }
-
-    // Test 2: Scaling error weight
-    for i in 0..4 {
-        let config = BenchmarkConfig::hamming_scaling_weight(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
-    }
-}
-
-// MMT-specific version of Goppa tests that includes MMT parameters
-pub fn run_all_goppa_tests_mmt(runs: usize) {
-    // Test 1: Scaling code size
-    for i in 0..4 {
-        let config = BenchmarkConfig::goppa_scaling_size(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
-    }
-
-    // Test 2: Scaling error correction capability
-    for i in 0..4 {
-        let config = BenchmarkConfig::goppa_scaling_weight(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
+#[allow(dead_code)]
+pub fn run_all_benchmarks(runs: usize) {
+    for spec in ALGORITHMS {
+        run_all_tests_for_algorithm(spec.name, runs);
     }
 }
 
-// MMT-specific version of QC tests that includes MMT parameters
-pub fn run_all_qc_tests_mmt(runs: usize) {
-    // Test 1: Scaling code size
-    for i in 0..4 {
-        let config = BenchmarkConfig::qc_scaling_size(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
-    }
-
-    // Test 2: Scaling error weight
-    for i in 0..4 {
-        let config = BenchmarkConfig::qc_scaling_weight(i)
-            .with_algorithm("mmt")
-            .with_runs(runs)
-            .with_mmt_params(2, 256, 256); // Default MMT parameters
-        run_benchmark(config);
+#[allow(dead_code)]
+pub fn run_real_world_test(algorithm: &str, runs: usize) {
+    let Some(spec) = find_algorithm(algorithm) else {
+        eprintln!("Unknown algorithm: {}", algorithm);
+        return;
+    };
+    let supports = |config: &BenchmarkConfig| spec.code_types.contains(&config.code_type.as_str());
+    for i in 0..2 {
+        // Just test first two levels as higher ones might be too slow
+        for config in [
+            BenchmarkConfig::real_world_goppa(i),
+            BenchmarkConfig::real_world_qc(i),
+        ] {
+            if supports(&config) {
+                run_benchmark(config.with_algorithm(spec.name).with_runs(runs));
+            }
+        }
     }
 }
 
 #[allow(dead_code)]
-pub fn run_all_benchmarks(runs: usize) {
-    let algorithms = [
-        "prange",
-        "stern",
-        "lee_brickell",
-        "ball_collision",
-        "bjmm",
-        "patterson",
-    ];
-
-    for &algorithm in &algorithms {
-        run_all_tests_for_algorithm(algorithm, runs);
+pub fn run_dfr_benchmark(config: DfrConfig) {
+    if let Err(e) = run_dfr_sweep(&config) {
+        eprintln!("DFR sweep failed: {}", e);
     }
-
-    // Run MMT separately
-    run_all_mmt_tests(runs);
 }
 
+// DFR curves of every bit-flipping decoder on the same sweep
 #[allow(dead_code)]
-pub fn run_real_world_test(algorithm: &str, runs: usize) {
-    for i in 0..2 {
-        // Just test first two levels as higher ones might be too slow
-        let config = BenchmarkConfig::real_world_goppa(i)
-            .with_algorithm(algorithm)
-            .with_runs(runs);
-        run_benchmark(config);
-
-        let config = BenchmarkConfig::real_world_qc(i)
-            .with_algorithm(algorithm)
-            .with_runs(runs);
-        run_benchmark(config);
+pub fn run_all_dfr_benchmarks(decodings: usize, keys: usize) {
+    for decoder in ["gallager", "bgf", "step_by_step"] {
+        let config = DfrConfig::toy_sweep()
+            .with_decoder(decoder)
+            .with_decodings(decodings, keys);
+        run_dfr_benchmark(config);
     }
 }
@@ -1,36 +1,76 @@
-use crate::types::{BenchmarkConfig, BenchmarkResult, BenchmarkStats};
+use crate::algorithms::algorithm_utils::{
+    apply_errors, calculate_syndrome, generate_random_error_vector, is_valid_error, random_seed,
+    seeded_rng,
+};
+use crate::algorithms::registry::{decoder_by_name, find_algorithm};
+use crate::code_generator::generate_code;
+use crate::types::{
+    BenchmarkConfig, BenchmarkResult, BenchmarkStats, DecodeInput, DecodeTarget, PqcatError,
+    RunIsolation,
+};
 use csv::Writer;
+use rand::Rng;
 use regex::Regex;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use sysinfo::System;
+use std::time::Instant;
 
 pub fn extract_time(output: &str) -> Option<u64> {
     let re = Regex::new(r"Time:\s*(\d+)\s*μs").unwrap();
-    if let Some(captures) = re.captures(output) {
-        if let Some(time_str) = captures.get(1) {
-            return time_str.as_str().parse::<u64>().ok();
-        }
+    if let Some(captures) = re.captures(output)
+        && let Some(time_str) = captures.get(1)
+    {
+        return time_str.as_str().parse::<u64>().ok();
     }
     None
 }
 
 pub fn extract_memory(output: &str) -> Option<u64> {
     let re = Regex::new(r"Peak memory:\s*(\d+)\s*KiB").unwrap();
-    if let Some(captures) = re.captures(output) {
-        if let Some(mem_str) = captures.get(1) {
-            return mem_str.as_str().parse::<u64>().ok();
-        }
+    if let Some(captures) = re.captures(output)
+        && let Some(mem_str) = captures.get(1)
+    {
+        return mem_str.as_str().parse::<u64>().ok();
+    }
+    None
+}
+
+pub fn extract_iterations(output: &str) -> Option<u64> {
+    let re = Regex::new(r"Iterations:\s*(\d+)").unwrap();
+    if let Some(captures) = re.captures(output)
+        && let Some(iter_str) = captures.get(1)
+    {
+        return iter_str.as_str().parse::<u64>().ok();
+    }
+    None
+}
+
+/// Whether the report's "Result:" line says success; other lines may mention the word too
+pub fn extract_result(output: &str) -> Option<bool> {
+    let re = Regex::new(r"(?m)^Result:\s*(\w+)").unwrap();
+    if let Some(captures) = re.captures(output)
+        && let Some(result_str) = captures.get(1)
+    {
+        return Some(result_str.as_str() == "success");
+    }
+    None
+}
+
+pub fn extract_success_probability(output: &str) -> Option<f64> {
+    let re = Regex::new(r"Theoretical success probability per iteration:\s*([0-9.eE+-]+)").unwrap();
+    if let Some(captures) = re.captures(output)
+        && let Some(p_str) = captures.get(1)
+    {
+        return p_str.as_str().parse::<f64>().ok();
     }
     None
 }
 
 pub fn ensure_results_directory() {
     if !Path::new("./results").exists() {
-        fs::create_dir("./results")
-            .expect("Failed to create results directory");
+        fs::create_dir("./results").expect("Failed to create results directory");
         fs::create_dir("./results/txt").expect("Failed to create txt directory");
         fs::create_dir("./results/csv").expect("Failed to create csv directory");
     }
@@ -46,7 +86,7 @@ pub fn create_output_files(config: &BenchmarkConfig) -> (Writer<File>, String) {
     let mut writer = Writer::from_writer(file);
 
     writer
-        .write_record(["Run", "Time (μs)", "Memory (KiB)", "Result"])
+        .write_record(["Run", "Seed", "Time (μs)", "Memory (KiB)", "Result"])
         .expect("Failed to write CSV headers");
 
     let txt_filename = format!(
@@ -57,10 +97,17 @@ pub fn create_output_files(config: &BenchmarkConfig) -> (Writer<File>, String) {
     (writer, txt_filename)
 }
 
-pub fn execute_benchmark_runs(config: &BenchmarkConfig) -> Vec<BenchmarkResult> {
+/// Run the benchmark and write one CSV row per completed run. Run seeds are drawn from the
+/// benchmark's seed, so each row's seed replays that run on its own
+pub fn execute_benchmark_runs(
+    config: &BenchmarkConfig,
+    writer: &mut Writer<File>,
+) -> Vec<BenchmarkResult> {
+    let mut seeds = seeded_rng(config.seed.unwrap_or_else(random_seed));
     let mut results = Vec::with_capacity(config.runs);
     for run in 1..=config.runs {
-        match execute_single_run(config, run) {
+        let seed = seeds.random();
+        match execute_single_run(config, run, seed) {
             Some(result) => {
                 println!(
                     "Run {}/{}: Time = {} μs, Memory = {} KiB, Result = {}",
@@ -71,6 +118,15 @@ pub fn execute_benchmark_runs(config: &BenchmarkConfig) -> Vec<BenchmarkResult>
                     if result.success { "success" } else { "fail" }
                 );
 
+                writer
+                    .write_record([
+                        run.to_string(),
+                        result.seed.to_string(),
+                        result.duration.to_string(),
+                        result.memory.to_string(),
+                        if result.success { "success" } else { "fail" }.to_string(),
+                    ])
+                    .expect("Failed to write CSV row");
                 results.push(result);
             }
             None => continue, // Skip failed runs
@@ -80,14 +136,96 @@ pub fn execute_benchmark_runs(config: &BenchmarkConfig) -> Vec<BenchmarkResult>
     results
 }
 
-pub fn execute_single_run(config: &BenchmarkConfig, run: usize) -> Option<BenchmarkResult> {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+pub fn execute_single_run(
+    config: &BenchmarkConfig,
+    run: usize,
+    seed: u64,
+) -> Option<BenchmarkResult> {
+    match config.isolation {
+        RunIsolation::InProcess => execute_in_process(config, run, seed),
+        RunIsolation::Subprocess => execute_in_subprocess(config, run, seed),
+    }
+}
+
+/// Decode one random error through the library. Code generation and error sampling are left out of
+/// the timing; the memory figure is the decoder's own RSS delta, which shares the process with
+/// earlier runs, so use subprocess isolation when memory matters. Draws from the generator in the
+/// same order as the CLI, so `--seed` replays the run there
+pub fn execute_in_process(
+    config: &BenchmarkConfig,
+    run: usize,
+    seed: u64,
+) -> Option<BenchmarkResult> {
+    match decode_in_process(config, seed) {
+        Ok(result) => Some(result),
+        Err(e) => {
+            eprintln!("Run {} (seed {}) failed: {}", run, seed, e);
+            None
+        }
+    }
+}
+
+fn decode_in_process(config: &BenchmarkConfig, seed: u64) -> Result<BenchmarkResult, PqcatError> {
+    let decoder = decoder_by_name(&config.algorithm_name, &config.partition_params())?;
+    let mut rng = seeded_rng(seed);
+
+    let (g, h, structured_key) = generate_code(
+        config.n,
+        config.k,
+        config.w,
+        config.code_type.clone(),
+        config.circulant_weight,
+        &mut rng,
+    )?;
+    let error = generate_random_error_vector(config.n, config.w, &mut rng)?;
+    let received = apply_errors(&g.row_vector(0).to_bits(), &error);
+    let error_syndrome = calculate_syndrome(&error, &h);
+    let input = DecodeInput {
+        h: &h,
+        target: DecodeTarget::Received(&received),
+        weight: config.w,
+        key: structured_key.as_ref(),
+    };
+
+    let start = Instant::now();
+    let outcome = decoder.decode(&input, &mut rng);
+    let duration = start.elapsed().as_micros() as u64;
+
+    // A decoder without its key never ran, so there is nothing to measure
+    let success = match &outcome.result {
+        Ok(decoded) => is_valid_error(decoded, &error_syndrome, &h, config.w),
+        Err(e @ PqcatError::Unsupported(_)) => return Err(e.clone()),
+        Err(_) => false,
+    };
 
-    let mut cmd = build_command(config);
+    Ok(BenchmarkResult {
+        seed,
+        duration,
+        memory: (outcome.metrics.peak_memory / 1024) as u64,
+        success,
+        iterations: outcome.metrics.iterations as u64,
+        success_probability: outcome.metrics.success_probability,
+    })
+}
+
+/// Run the pqcat binary once and scrape its report, so each run has a fresh process and its peak
+/// memory is measured from a clean heap
+pub fn execute_in_subprocess(
+    config: &BenchmarkConfig,
+    run: usize,
+    seed: u64,
+) -> Option<BenchmarkResult> {
+    let mut cmd = build_command(config, seed);
     cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
 
-    let child = cmd.spawn().expect("Failed to spawn process");
+    let child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Run {} failed to start {:?}: {}", run, cmd.get_program(), e);
+            return None;
+        }
+    };
     let output = match child.wait_with_output() {
         Ok(output) => output,
         Err(e) => {
@@ -98,28 +236,49 @@ pub fn execute_single_run(config: &BenchmarkConfig, run: usize) -> Option<Benchm
 
     if !output.status.success() {
         eprintln!(
-            "Run {} failed: {}",
+            "Run {} (seed {}) failed: {}",
             run,
-            String::from_utf8_lossy(&output.stderr)
+            seed,
+            String::from_utf8_lossy(&output.stderr).trim()
         );
         return None;
     }
 
     let stdout_str = String::from_utf8_lossy(&output.stdout);
-    let success = stdout_str.contains("success");
+    let success = extract_result(&stdout_str).unwrap_or(false);
 
     let duration = extract_time(&stdout_str).unwrap_or(0);
     let memory = extract_memory(&stdout_str).unwrap_or(0);
+    let iterations = extract_iterations(&stdout_str).unwrap_or(0);
+    let success_probability = extract_success_probability(&stdout_str);
 
     Some(BenchmarkResult {
+        seed,
         duration,
         memory,
         success,
+        iterations,
+        success_probability,
     })
 }
 
-pub fn build_command(config: &BenchmarkConfig) -> Command {
-    let mut cmd = Command::new("./target/release/pqcat");
+/// The pqcat binary built alongside the running executable (target/<profile>/, one level up for
+/// examples and tests), falling back to the release build relative to the working directory
+fn pqcat_binary() -> PathBuf {
+    let name = format!("pqcat{}", std::env::consts::EXE_SUFFIX);
+    if let Ok(exe) = std::env::current_exe() {
+        for dir in exe.ancestors().skip(1).take(2) {
+            let candidate = dir.join(&name);
+            if candidate.is_file() {
+                return candidate;
+            }
+        }
+    }
+    Path::new("./target/release").join(name)
+}
+
+pub fn build_command(config: &BenchmarkConfig, seed: u64) -> Command {
+    let mut cmd = Command::new(pqcat_binary());
 
     cmd.arg(config.algorithm_name.as_str());
 
@@ -129,23 +288,26 @@ pub fn build_command(config: &BenchmarkConfig) -> Command {
         .arg("--k")
         .arg(config.k.to_string())
         .arg("--w")
-        .arg(config.w.to_string());
-
-    // Add code type parameter except for Patterson (which is always Goppa)
-    if config.algorithm_name != "patterson" {
-        cmd.arg("--code-type").arg(&config.code_type);
-    }
-
-    // Add MMT-specific parameters if needed
-    if config.algorithm_name == "mmt" {
-        if let Some(p) = config.p {
-            cmd.arg("--p").arg(p.to_string());
+        .arg(config.w.to_string())
+        .arg("--seed")
+        .arg(seed.to_string());
+
+    // Add the code type and the parameters the algorithm declares in the registry; unknown
+    // algorithms are passed through for the CLI to reject
+    if let Some(spec) = find_algorithm(&config.algorithm_name) {
+        if spec.code_types.len() > 1 {
+            cmd.arg("--code-type").arg(&config.code_type);
         }
-        if let Some(l1) = config.l1 {
-            cmd.arg("--l1").arg(l1.to_string());
+        if let Some(d) = config.circulant_weight
+            && spec.code_types.contains(&"qc")
+        {
+            cmd.arg("--d").arg(d.to_string());
         }
-        if let Some(l2) = config.l2 {
-            cmd.arg("--l2").arg(l2.to_string());
+        let params = config.partition_params();
+        for param in spec.params {
+            if let Some(value) = params.get(param.name) {
+                cmd.arg(format!("--{}", param.name)).arg(value.to_string());
+            }
         }
     }
 
@@ -166,6 +328,9 @@ pub fn calculate_statistics(results: &[BenchmarkResult]) -> BenchmarkStats {
             time_ci_upper: 0.0,
             memory_ci_lower: 0.0,
             memory_ci_upper: 0.0,
+            mean_iterations: 0.0,
+            measured_success_probability: 0.0,
+            theoretical_success_probability: None,
         };
     }
 
@@ -177,14 +342,14 @@ pub fn calculate_statistics(results: &[BenchmarkResult]) -> BenchmarkStats {
     memories.sort();
 
     // Calculate medians
-    let median_time = if completed_runs % 2 == 0 {
+    let median_time = if completed_runs.is_multiple_of(2) {
         let mid = completed_runs / 2;
         (durations[mid - 1] + durations[mid]) as f64 / 2.0
     } else {
         durations[completed_runs / 2] as f64
     };
 
-    let median_memory = if completed_runs % 2 == 0 {
+    let median_memory = if completed_runs.is_multiple_of(2) {
         let mid = completed_runs / 2;
         (memories[mid - 1] + memories[mid]) as f64 / 2.0
     } else {
@@ -215,6 +380,24 @@ pub fn calculate_statistics(results: &[BenchmarkResult]) -> BenchmarkStats {
 
     let successful_runs = results.iter().filter(|r| r.success).count();
 
+    // Each run is a sequence of independent iterations, so successes per iteration estimates
+    // the per-iteration success probability
+    let total_iterations: u64 = results.iter().map(|r| r.iterations).sum();
+    let measured_success_probability = if total_iterations > 0 {
+        successful_runs as f64 / total_iterations as f64
+    } else {
+        0.0
+    };
+    let reported: Vec<f64> = results
+        .iter()
+        .filter_map(|r| r.success_probability)
+        .collect();
+    let theoretical_success_probability = if reported.is_empty() {
+        None
+    } else {
+        Some(reported.iter().sum::<f64>() / reported.len() as f64)
+    };
+
     BenchmarkStats {
         median_time,
         median_memory,
@@ -225,6 +408,9 @@ pub fn calculate_statistics(results: &[BenchmarkResult]) -> BenchmarkStats {
         time_ci_upper: time_ci_upper_diff,
         memory_ci_lower: memory_ci_lower_diff,
         memory_ci_upper: memory_ci_upper_diff,
+        mean_iterations: total_iterations as f64 / completed_runs as f64,
+        measured_success_probability,
+        theoretical_success_probability,
     }
 }
 
@@ -246,6 +432,15 @@ pub fn write_results_to_file(
         config.n, config.k, config.w
     )
     .unwrap();
+    writeln!(
+        txt_file,
+        "Isolation: {}",
+        format_isolation(config.isolation)
+    )
+    .unwrap();
+    if let Some(seed) = config.seed {
+        writeln!(txt_file, "Seed: {}", seed).unwrap();
+    }
     writeln!(
         txt_file,
         "Runs Completed: {}/{}",
@@ -270,6 +465,28 @@ pub fn write_results_to_file(
         stats.success_rate, stats.successful_runs, stats.completed_runs
     )
     .unwrap();
+    writeln!(txt_file, "Mean Iterations: {:.2}", stats.mean_iterations).unwrap();
+    writeln!(
+        txt_file,
+        "Success Probability per Iteration: {:.6e} measured{}",
+        stats.measured_success_probability,
+        format_theoretical(stats.theoretical_success_probability)
+    )
+    .unwrap();
+}
+
+fn format_isolation(isolation: RunIsolation) -> &'static str {
+    match isolation {
+        RunIsolation::InProcess => "in-process (decoding time only)",
+        RunIsolation::Subprocess => "subprocess per run",
+    }
+}
+
+fn format_theoretical(probability: Option<f64>) -> String {
+    match probability {
+        Some(p) => format!(", {:.6e} theoretical", p),
+        None => String::new(),
+    }
 }
 
 pub fn print_summary(config: &BenchmarkConfig, stats: &BenchmarkStats) {
@@ -288,7 +505,12 @@ pub fn print_summary(config: &BenchmarkConfig, stats: &BenchmarkStats) {
         stats.median_memory, stats.memory_ci_lower, stats.memory_ci_upper
     );
     println!(
-        "Success Rate: {:.2}% ({}/{})\n\n",
+        "Success Rate: {:.2}% ({}/{})",
         stats.success_rate, stats.successful_runs, stats.completed_runs
     );
+    println!(
+        "Success Probability per Iteration: {:.6e} measured{}\n\n",
+        stats.measured_success_probability,
+        format_theoretical(stats.theoretical_success_probability)
+    );
 }
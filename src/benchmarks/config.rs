@@ -1,4 +1,5 @@
-use crate::types::BenchmarkConfig;
+use crate::codes::qc_mdpc::prime_block_sizes;
+use crate::types::{BenchmarkConfig, DfrConfig, FlipRule, PartitionParams, RunIsolation};
 
 #[allow(dead_code)]
 impl BenchmarkConfig {
@@ -76,10 +77,10 @@ impl BenchmarkConfig {
     // Quasi-Cyclic code with scaling code size
     pub fn qc_scaling_size(size_index: usize) -> Self {
         let params = [
-            (30, 20, 2), // (n, k, w)
-            (60, 40, 2),
-            (90, 60, 2),
-            (120, 80, 2),
+            (33, 22, 2), // (n, k, w) with n = 3r, k = 2r for prime r, 2 primitive modulo r
+            (57, 38, 2),
+            (87, 58, 2),
+            (111, 74, 2),
         ];
         let (n, k, w) = params[size_index];
 
@@ -98,8 +99,8 @@ impl BenchmarkConfig {
         let w = weights[weight_index];
 
         Self {
-            n: 60,
-            k: 40,
+            n: 57,
+            k: 38,
             w,
             code_type: "qc".to_string(),
             ..Self::default()
@@ -112,14 +113,20 @@ impl BenchmarkConfig {
     pub fn mmt_config(n: usize, k: usize, w: usize, code_type: &str) -> Self {
         Self {
             runs: 100,
+            isolation: RunIsolation::InProcess,
+            seed: None,
             algorithm_name: "mmt".to_string(),
             n,
             k,
             w,
             code_type: code_type.to_string(),
+            circulant_weight: None,
             p: Some(2),
-            l1: Some(256),
-            l2: Some(256),
+            l: None,
+            l1: Some(1),
+            l2: Some(2),
+            eps1: None,
+            eps2: None,
         }
     }
 
@@ -127,41 +134,56 @@ impl BenchmarkConfig {
 
     // Real-world Goppa parameters (adjusted for field size and constraints)
     pub fn real_world_goppa(security_level: usize) -> Self {
+        // From m = 12 on, use the fields Classic McEliece is defined over
         let params = [
-            (2047, 1695, 27),  // ~80-bit classical security (reduced)
-            (3487, 2719, 64),  // ~128-bit classical / NIST Level 1 (reduced)
-            (4095, 3359, 96),  // ~192-bit classical / NIST Level 3 (reduced)
-            (6939, 5412, 119), // ~256-bit classical / NIST Level 5 (reduced)
+            (2047, 1695, 27, "goppa"),           // ~80-bit classical security (reduced)
+            (3487, 2719, 64, "goppa_mceliece"),  // ~128-bit classical / NIST Level 1 (reduced)
+            (4095, 3359, 96, "goppa_mceliece"),  // ~192-bit classical / NIST Level 3 (reduced)
+            (6939, 5412, 119, "goppa_mceliece"), // ~256-bit classical / NIST Level 5 (reduced)
         ];
-        let (n, k, w) = params[security_level];
+        let (n, k, w, code_type) = params[security_level];
 
         Self {
             n,
             k,
             w,
-            code_type: "goppa".to_string(),
+            code_type: code_type.to_string(),
             ..Self::default()
         }
     }
 
-    // Real-world QC-MDPC parameters (adjusted for field size and QC constraints)
+    // Real-world QC-MDPC parameters: BIKE's (r, d, t) with n = 2r, k = r and d the weight of each
+    // of the two circulant blocks
     pub fn real_world_qc(security_level: usize) -> Self {
         let params = [
-            (8190, 4095, 142),  // NIST Level 1 (n=2*4095, k=1*4095)
-            (16382, 8191, 159), // NIST Level 3 (n=2*8191, k=1*8191)
-            (24573, 8191, 199), // NIST Level 5 (n=3*8191, k=1*8191)
+            (12323, 71, 134),  // BIKE Level 1
+            (24659, 103, 199), // BIKE Level 3
+            (40973, 137, 264), // BIKE Level 5
         ];
-        let (n, k, w) = params[security_level];
+        let (r, d, t) = params[security_level];
 
         Self {
-            n,
-            k,
-            w,
+            n: 2 * r,
+            k: r,
+            w: t,
             code_type: "qc".to_string(),
+            circulant_weight: Some(d),
             ..Self::default()
         }
     }
 
+    // Algorithm parameters in the form the registry and the decoders take them
+    pub fn partition_params(&self) -> PartitionParams {
+        PartitionParams {
+            p: self.p,
+            l: self.l,
+            l1: self.l1,
+            l2: self.l2,
+            eps1: self.eps1,
+            eps2: self.eps2,
+        }
+    }
+
     // ==================== BUILDER METHODS ====================
 
     // Set algorithm
@@ -176,6 +198,44 @@ impl BenchmarkConfig {
         self
     }
 
+    // Run every decoding in its own pqcat process, e.g. for memory measurements
+    pub fn with_subprocess_isolation(mut self) -> Self {
+        self.isolation = RunIsolation::Subprocess;
+        self
+    }
+
+    // Set the weight of each QC-MDPC circulant instead of the BIKE-scaled default
+    pub fn with_circulant_weight(mut self, circulant_weight: usize) -> Self {
+        self.circulant_weight = Some(circulant_weight);
+        self
+    }
+
+    // Fix the seed the runs draw theirs from, to replay a whole benchmark
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Set Stern parameters
+    pub fn with_stern_params(mut self, p: usize, l: usize) -> Self {
+        self.p = Some(p);
+        self.l = Some(l);
+        self
+    }
+
+    // Set Dumer parameters
+    pub fn with_dumer_params(mut self, p: usize, l: usize) -> Self {
+        self.p = Some(p);
+        self.l = Some(l);
+        self
+    }
+
+    // Set Lee-Brickell parameters
+    pub fn with_lee_brickell_params(mut self, p: usize) -> Self {
+        self.p = Some(p);
+        self
+    }
+
     // Set MMT parameters
     pub fn with_mmt_params(mut self, p: usize, l1: usize, l2: usize) -> Self {
         self.p = Some(p);
@@ -183,4 +243,98 @@ impl BenchmarkConfig {
         self.l2 = Some(l2);
         self
     }
+
+    // Set BJMM parameters
+    pub fn with_bjmm_params(
+        mut self,
+        p: usize,
+        eps1: usize,
+        eps2: usize,
+        l1: usize,
+        l2: usize,
+    ) -> Self {
+        self.p = Some(p);
+        self.eps1 = Some(eps1);
+        self.eps2 = Some(eps2);
+        self.l1 = Some(l1);
+        self.l2 = Some(l2);
+        self
+    }
+}
+
+#[allow(dead_code)]
+impl DfrConfig {
+    // BIKE Level 1 weights (d = 71, t = 134) on block sizes small enough for failures to be
+    // observable, extrapolated to the specified r = 12323
+    pub fn bike_level1_sweep() -> Self {
+        Self {
+            decoder: FlipRule::BlackGrayFlip,
+            block_sizes: prime_block_sizes(9800, 10800, 5),
+            index: 2,
+            circulant_weight: 71,
+            t: 134,
+            decodings: 1_000_000,
+            keys: 10,
+            max_recorded_failures: 1000,
+            target_r: Some(12323),
+            seed: None,
+        }
+    }
+
+    // Small sweep that shows the whole waterfall within seconds
+    pub fn toy_sweep() -> Self {
+        Self {
+            decoder: FlipRule::BlackGrayFlip,
+            block_sizes: prime_block_sizes(450, 650, 5),
+            index: 2,
+            circulant_weight: 15,
+            t: 20,
+            decodings: 10_000,
+            keys: 4,
+            max_recorded_failures: 100,
+            target_r: Some(1000),
+            seed: None,
+        }
+    }
+
+    // ==================== BUILDER METHODS ====================
+
+    // Set decoder from its algorithm name (gallager, bgf or step_by_step)
+    pub fn with_decoder(mut self, name: &str) -> Self {
+        self.decoder = FlipRule::from_name(name)
+            .unwrap_or_else(|| panic!("{} is not a bit-flipping decoder", name));
+        self
+    }
+
+    // Set block sizes to sweep
+    pub fn with_block_sizes(mut self, block_sizes: Vec<usize>) -> Self {
+        self.block_sizes = block_sizes;
+        self
+    }
+
+    // Set circulant and error weights
+    pub fn with_weights(mut self, circulant_weight: usize, t: usize) -> Self {
+        self.circulant_weight = circulant_weight;
+        self.t = t;
+        self
+    }
+
+    // Set number of decodings and keys per block size
+    pub fn with_decodings(mut self, decodings: usize, keys: usize) -> Self {
+        self.decodings = decodings;
+        self.keys = keys;
+        self
+    }
+
+    // Set block size to extrapolate to
+    pub fn with_target_r(mut self, target_r: usize) -> Self {
+        self.target_r = Some(target_r);
+        self
+    }
+
+    // Fix the seed the block sizes draw theirs from, to replay a whole sweep
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 }
@@ -0,0 +1,433 @@
+use crate::algorithms::algorithm_utils::{random_seed, seeded_rng};
+use crate::algorithms::bit_flipping::{ParityChecks, flip_until_decoded};
+use crate::benchmarks::benchmark_utils::ensure_results_directory;
+use crate::codes::qc_mdpc::is_prime;
+use crate::types::{Circulant, DfrConfig, DfrFit, DfrPoint, PqcatError, QcMdpcKey};
+use csv::Writer;
+use rand::Rng;
+use rand::seq::index::sample;
+use std::fs::File;
+use std::io::Write;
+use std::thread;
+use std::time::Instant;
+
+const WATERFALL_DFR: f64 = 0.1; // Points failing at least this often are still in the waterfall
+const BATCH_DECODINGS: usize = 1000; // Decodings per separately seeded batch
+
+// Failures and iteration count of one worker's share of the decodings
+struct WorkerTally {
+    failures: usize,
+    iterations: usize,
+    recorded: Vec<Vec<usize>>,
+}
+
+// The decoders only see H, so unlike key generation there is no need for an invertible H0
+fn draw_key(config: &DfrConfig, r: usize, rng: &mut impl Rng) -> QcMdpcKey {
+    QcMdpcKey {
+        r,
+        circulant_weight: config.circulant_weight,
+        blocks: (0..config.index)
+            .map(|_| Circulant::random_sparse(r, config.circulant_weight, rng))
+            .collect(),
+    }
+}
+
+// Decode `decodings` uniformly random errors of weight t; by linearity the codeword does not
+// matter, so each decoding starts from the syndrome of the error alone
+fn decode_batch(
+    config: &DfrConfig,
+    key: &QcMdpcKey,
+    checks: &ParityChecks,
+    decodings: usize,
+    seed: u64,
+) -> WorkerTally {
+    let mut rng = seeded_rng(seed);
+    let n = key.r * key.blocks.len();
+    let mut tally = WorkerTally {
+        failures: 0,
+        iterations: 0,
+        recorded: Vec::new(),
+    };
+
+    for _ in 0..decodings {
+        let mut support = sample(&mut rng, n, config.t).into_vec();
+        support.sort_unstable();
+        let mut syndrome = checks.syndrome_of_support(&support);
+        let mut error = vec![0; n];
+
+        tally.iterations += flip_until_decoded(
+            config.decoder,
+            checks,
+            key,
+            config.t,
+            &mut error,
+            &mut syndrome,
+            &mut rng,
+        );
+
+        // A zero syndrome with a different error is a decoding to another coset member, which
+        // counts as a failure just like running out of iterations
+        let decoded = syndrome.iter().all(|&bit| bit == 0)
+            && error.iter().filter(|&&bit| bit == 1).count() == support.len()
+            && support.iter().all(|&j| error[j] == 1);
+        if !decoded {
+            tally.failures += 1;
+            if tally.recorded.len() < config.max_recorded_failures {
+                tally.recorded.push(support);
+            }
+        }
+    }
+
+    tally
+}
+
+// Run all decodings for block size r, spreading each key's share over every available core. The
+// share is cut into seeded batches, so the same seed gives the same point on any number of cores
+fn measure_point(config: &DfrConfig, r: usize, seed: u64) -> DfrPoint {
+    let mut rng = seeded_rng(seed);
+    let workers = thread::available_parallelism().map_or(1, |p| p.get());
+    let keys = config.keys.max(1);
+    let mut point = DfrPoint {
+        r,
+        seed,
+        decodings: 0,
+        failures: 0,
+        mean_iterations: 0.0,
+        recorded_failures: Vec::new(),
+        keys: Vec::with_capacity(keys),
+    };
+    let mut iterations = 0;
+
+    for key_index in 0..keys {
+        let key = draw_key(config, r, &mut rng);
+        let checks = ParityChecks::from_key(&key);
+        let per_key = config.decodings / keys + usize::from(key_index < config.decodings % keys);
+        let batch_seeds: Vec<u64> = (0..per_key.div_ceil(BATCH_DECODINGS))
+            .map(|_| rng.random())
+            .collect();
+
+        let tallies: Vec<WorkerTally> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|worker| {
+                    let (key, checks, batch_seeds) = (&key, &checks, &batch_seeds);
+                    scope.spawn(move || {
+                        (worker..batch_seeds.len())
+                            .step_by(workers)
+                            .map(|batch| {
+                                let decodings =
+                                    BATCH_DECODINGS.min(per_key - batch * BATCH_DECODINGS);
+                                let tally = decode_batch(
+                                    config,
+                                    key,
+                                    checks,
+                                    decodings,
+                                    batch_seeds[batch],
+                                );
+                                (batch, tally)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            let mut tallies: Vec<(usize, WorkerTally)> = handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("DFR worker panicked"))
+                .collect();
+            tallies.sort_by_key(|&(batch, _)| batch);
+            tallies.into_iter().map(|(_, tally)| tally).collect()
+        });
+
+        for tally in tallies {
+            point.failures += tally.failures;
+            iterations += tally.iterations;
+            let room = config
+                .max_recorded_failures
+                .saturating_sub(point.recorded_failures.len());
+            point.recorded_failures.extend(
+                tally
+                    .recorded
+                    .into_iter()
+                    .take(room)
+                    .map(|support| (key_index, support)),
+            );
+        }
+        point.decodings += per_key;
+        point.keys.push(key);
+    }
+
+    if point.decodings > 0 {
+        point.mean_iterations = iterations as f64 / point.decodings as f64;
+    }
+    point
+}
+
+impl DfrPoint {
+    pub fn dfr(&self) -> f64 {
+        if self.decodings == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.decodings as f64
+        }
+    }
+}
+
+impl DfrFit {
+    pub fn log10_dfr(&self, r: usize) -> f64 {
+        self.intercept + self.slope * r as f64
+    }
+
+    // Smallest block size the fitted curve puts at or below DFR = 2^-lambda
+    pub fn block_size_for_security(&self, lambda: usize) -> Option<usize> {
+        if self.slope >= 0.0 {
+            return None;
+        }
+        let target = -(lambda as f64) * 2f64.log10();
+        Some(((target - self.intercept) / self.slope).ceil().max(0.0) as usize)
+    }
+}
+
+// Past the waterfall region log(DFR) decreases linearly in r, so the tail is extrapolated from a
+// line fitted in log scale. Points still in the waterfall, or without failures to measure, are left out
+pub fn fit_dfr_curve(points: &[DfrPoint]) -> Option<DfrFit> {
+    let samples: Vec<(f64, f64)> = points
+        .iter()
+        .filter(|point| point.failures > 0 && point.dfr() < WATERFALL_DFR)
+        .map(|point| (point.r as f64, point.dfr().log10()))
+        .collect();
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let count = samples.len() as f64;
+    let mean_r = samples.iter().map(|&(r, _)| r).sum::<f64>() / count;
+    let mean_log = samples.iter().map(|&(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = samples
+        .iter()
+        .map(|&(r, y)| (r - mean_r) * (y - mean_log))
+        .sum();
+    let variance: f64 = samples.iter().map(|&(r, _)| (r - mean_r).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance;
+    Some(DfrFit {
+        slope,
+        intercept: mean_log - slope * mean_r,
+        points_used: samples.len(),
+    })
+}
+
+fn validate(config: &DfrConfig) -> Result<(), PqcatError> {
+    if config.index < 2 {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid DFR sweep: need at least 2 circulant blocks, got {}",
+            config.index
+        )));
+    }
+    if config.circulant_weight.is_multiple_of(2) {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid DFR sweep: circulant weight ({}) must be odd",
+            config.circulant_weight
+        )));
+    }
+    for &r in &config.block_sizes {
+        if !is_prime(r) || config.circulant_weight >= r || config.t > config.index * r {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Invalid DFR sweep: block size {} must be a prime above d = {} with t = {} <= n",
+                r, config.circulant_weight, config.t
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn output_stem(config: &DfrConfig) -> String {
+    format!(
+        "dfr_{}_l{}_d{}_t{}",
+        config.decoder.name(),
+        config.index,
+        config.circulant_weight,
+        config.t
+    )
+}
+
+fn write_dfr_results(config: &DfrConfig, seed: u64, points: &[DfrPoint], fit: Option<&DfrFit>) {
+    ensure_results_directory();
+    let stem = output_stem(config);
+
+    let file =
+        File::create(format!("./results/csv/{}.csv", stem)).expect("Failed to create CSV file");
+    let mut writer = Writer::from_writer(file);
+    writer
+        .write_record([
+            "r",
+            "n",
+            "Seed",
+            "Decodings",
+            "Failures",
+            "DFR",
+            "Mean Iterations",
+        ])
+        .expect("Failed to write CSV headers");
+    for point in points {
+        writer
+            .write_record([
+                point.r.to_string(),
+                (point.r * config.index).to_string(),
+                point.seed.to_string(),
+                point.decodings.to_string(),
+                point.failures.to_string(),
+                format!("{:.6e}", point.dfr()),
+                format!("{:.2}", point.mean_iterations),
+            ])
+            .expect("Failed to write CSV row");
+    }
+    writer.flush().expect("Failed to flush CSV writer");
+
+    // Failing error vectors only make sense together with the key they failed on
+    let file = File::create(format!("./results/csv/{}_failures.csv", stem))
+        .expect("Failed to create CSV file");
+    let mut writer = Writer::from_writer(file);
+    writer
+        .write_record(["r", "Key", "Error Support"])
+        .expect("Failed to write CSV headers");
+    for point in points {
+        for (key_index, support) in &point.recorded_failures {
+            writer
+                .write_record([
+                    point.r.to_string(),
+                    key_index.to_string(),
+                    support
+                        .iter()
+                        .map(|j| j.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ])
+                .expect("Failed to write CSV row");
+        }
+    }
+    writer.flush().expect("Failed to flush CSV writer");
+
+    let file = File::create(format!("./results/csv/{}_keys.csv", stem))
+        .expect("Failed to create CSV file");
+    let mut writer = Writer::from_writer(file);
+    writer
+        .write_record(["r", "Key", "Block", "Support"])
+        .expect("Failed to write CSV headers");
+    for point in points {
+        for (key_index, key) in point.keys.iter().enumerate() {
+            if !point.recorded_failures.iter().any(|&(k, _)| k == key_index) {
+                continue;
+            }
+            for (block_index, block) in key.blocks.iter().enumerate() {
+                writer
+                    .write_record([
+                        point.r.to_string(),
+                        key_index.to_string(),
+                        block_index.to_string(),
+                        block
+                            .support()
+                            .iter()
+                            .map(|j| j.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ])
+                    .expect("Failed to write CSV row");
+            }
+        }
+    }
+    writer.flush().expect("Failed to flush CSV writer");
+
+    let mut txt_file =
+        File::create(format!("./results/txt/{}.txt", stem)).expect("Failed to create TXT file");
+    writeln!(txt_file, "Algorithm: {}", config.decoder.name()).unwrap();
+    writeln!(txt_file, "Code Type: qc").unwrap();
+    writeln!(
+        txt_file,
+        "Parameters: l={}, d={}, w={}, t={}",
+        config.index,
+        config.circulant_weight,
+        config.index * config.circulant_weight,
+        config.t
+    )
+    .unwrap();
+    writeln!(txt_file, "Seed: {}", seed).unwrap();
+    for point in points {
+        writeln!(
+            txt_file,
+            "r={}: DFR {:.6e} ({} of {} decodings failed, {:.2} mean iterations)",
+            point.r,
+            point.dfr(),
+            point.failures,
+            point.decodings,
+            point.mean_iterations
+        )
+        .unwrap();
+    }
+    match fit {
+        Some(fit) => {
+            writeln!(
+                txt_file,
+                "Fit: log10(DFR) = {:.6} + {:.6e} * r over {} points",
+                fit.intercept, fit.slope, fit.points_used
+            )
+            .unwrap();
+            if let Some(target_r) = config.target_r {
+                writeln!(
+                    txt_file,
+                    "Extrapolated DFR at r={}: 10^{:.2}",
+                    target_r,
+                    fit.log10_dfr(target_r)
+                )
+                .unwrap();
+            }
+            if let Some(r) = fit.block_size_for_security(128) {
+                writeln!(txt_file, "DFR 2^-128 reached at r >= {}", r).unwrap();
+            }
+        }
+        None => writeln!(
+            txt_file,
+            "Fit: fewer than 2 points past the waterfall with failures"
+        )
+        .unwrap(),
+    }
+}
+
+pub fn run_dfr_sweep(config: &DfrConfig) -> Result<(Vec<DfrPoint>, Option<DfrFit>), PqcatError> {
+    validate(config)?;
+
+    // Each block size gets its own seed, recorded with its point, so it can be replayed alone
+    let seed = config.seed.unwrap_or_else(random_seed);
+    let mut seeds = seeded_rng(seed);
+    let mut points = Vec::with_capacity(config.block_sizes.len());
+    for &r in &config.block_sizes {
+        let start_time = Instant::now();
+        let point = measure_point(config, r, seeds.random());
+        println!(
+            "{} r={}: DFR = {:.6e} ({}/{}) in {:.1} s",
+            config.decoder.name(),
+            r,
+            point.dfr(),
+            point.failures,
+            point.decodings,
+            start_time.elapsed().as_secs_f64()
+        );
+        points.push(point);
+    }
+
+    let fit = fit_dfr_curve(&points);
+    write_dfr_results(config, seed, &points, fit.as_ref());
+
+    if let Some(fit) = &fit
+        && let Some(target_r) = config.target_r
+    {
+        println!(
+            "Extrapolated DFR at r={}: 10^{:.2}\n",
+            target_r,
+            fit.log10_dfr(target_r)
+        );
+    }
+
+    Ok((points, fit))
+}
@@ -1,3 +1,4 @@
 pub mod benchmark_runner;
 pub mod benchmark_utils;
 pub mod config;
+pub mod dfr;
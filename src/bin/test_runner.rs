@@ -1,15 +1,18 @@
 #[allow(unused_imports)]
 use pqcat::benchmarks::benchmark_runner::{
-    run_all_benchmarks, run_all_goppa_tests, run_all_hamming_tests, run_all_mmt_tests,
-    run_all_qc_tests, run_all_tests_for_algorithm, run_benchmark, run_real_world_test,
+    run_all_benchmarks, run_all_dfr_benchmarks, run_all_goppa_tests, run_all_hamming_tests,
+    run_all_qc_tests, run_all_tests_for_algorithm, run_benchmark, run_dfr_benchmark,
+    run_real_world_test,
 };
 #[allow(unused_imports)]
-use pqcat::types::BenchmarkConfig;
+use pqcat::types::{BenchmarkConfig, DfrConfig};
 
 fn main() {
     // Option 1: Run a single test case (e.g scaling size of the Hamming code)
     // let config = BenchmarkConfig::hamming_scaling_size(1).with_algorithm("prange").with_runs(100);
     // run_benchmark(config);
+    // Runs call the decoders in-process and time only the decoding; for memory figures from a fresh
+    // process per run (needs a release build of pqcat), add .with_subprocess_isolation()
 
     // Option 2: Run all tests for one algorithm (scaling size, scaling weight for Hamming, Goppa and QC)
     // run_all_tests_for_algorithm("prange", 100);
@@ -26,6 +29,11 @@ fn main() {
     // Option 5: Run real-world parameters (more in-line with practical use cases) for specific algorithms
     // run_real_world_test("prange", 100); - or combine with e.g. option 3 to run for multiple chose algorithms
 
+    // Option 6: Estimate the decoding failure rate of a bit-flipping decoder over a sweep of block sizes,
+    // run in-process on all cores and extrapolated in log scale (results/csv/dfr_*.csv)
+    // run_dfr_benchmark(DfrConfig::bike_level1_sweep().with_decoder("bgf"));
+    // run_all_dfr_benchmarks(10_000, 4);
+
     let algorithms = ["bjmm"];
     for &alg in &algorithms {
         run_real_world_test(alg, 1);
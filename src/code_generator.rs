@@ -1,131 +1,163 @@
-use crate::codes::code_utils::convert_to_systematic;
+use crate::codes::code_utils::{convert_to_systematic, is_orthogonal};
 use crate::codes::goppa::{generate_goppa_parity_matrix, generate_valid_goppa_params};
-use crate::types::GoppaParams;
-use ndarray::s;
-use ndarray::{Array2, Axis};
-use rand::seq::SliceRandom;
-use rand::{Rng, rng};
-use std::process;
-
-fn handle_code_result<T>(result: Result<T, String>, code_type: &str) -> T {
-    match result {
-        Ok(value) => value,
-        Err(e) => {
-            eprintln!("Error generating {} code: {}", code_type, e);
-            process::exit(1);
-        }
-    }
-}
-
+use crate::codes::qc_mdpc::{MAX_KEY_ATTEMPTS, default_circulant_weight, is_prime};
+use crate::types::{
+    BinaryMatrix, Circulant, GoppaField, GoppaParams, GoppaPolyKind, PqcatError, QcMdpcKey,
+    StructuredKey,
+};
+use rand::Rng;
+
+/// Generate a code of the given family with its private structure, if it has one. The codes are
+/// checked to be consistent, i.e. G·H^T = 0
 pub fn generate_code(
     n: usize,
     k: usize,
     w: usize,
     code_type: String,
-) -> (Array2<u8>, Array2<u8>, Option<GoppaParams>) {
-    match code_type.as_str() {
+    circulant_weight: Option<usize>,
+    rng: &mut impl Rng,
+) -> Result<(BinaryMatrix, BinaryMatrix, Option<StructuredKey>), PqcatError> {
+    if k == 0 || k >= n || w > n {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid code parameters: need 0 < k < n and w <= n, got n = {}, k = {}, w = {}",
+            n, k, w
+        )));
+    }
+
+    let (g, h, key) = match code_type.as_str() {
         "random" => {
-            let (g, h) = handle_code_result(generate_random_code(n, k), "random");
+            let (g, h) = generate_random_code(n, k, rng)?;
             (g, h, None)
         }
         "hamming" => {
-            let (g, h) = handle_code_result(generate_hamming_code(n, k), "hamming");
+            let (g, h) = generate_hamming_code(n, k)?;
             (g, h, None)
         }
-        "goppa" => {
-            let (g, h, goppa_params) = handle_code_result(generate_goppa_code(n, k, w), "goppa");
-            (g, h, Some(goppa_params))
+        "goppa" | "goppa_squarefree" | "goppa_mceliece" => {
+            let kind = match code_type.as_str() {
+                "goppa_squarefree" => GoppaPolyKind::SquareFree,
+                _ => GoppaPolyKind::Irreducible,
+            };
+            let field = match code_type.as_str() {
+                "goppa_mceliece" => GoppaField::ClassicMcEliece,
+                _ => GoppaField::Standard,
+            };
+            let (g, h, goppa_params) = generate_goppa_code(n, k, w, kind, field, rng)?;
+            (g, h, Some(StructuredKey::Goppa(goppa_params)))
         }
         "qc" => {
-            let (g, h) = handle_code_result(generate_qc_code(n, k), "qc");
-            (g, h, None)
+            let circulant_weight =
+                circulant_weight.unwrap_or_else(|| default_circulant_weight(n - k));
+            let (g, h, qc_key) = generate_qc_code(n, k, circulant_weight, rng)?;
+            (g, h, Some(StructuredKey::QcMdpc(qc_key)))
         }
         _ => {
-            eprintln!("Error: Unsupported code type '{}'", code_type);
-            process::exit(1);
+            return Err(PqcatError::Unsupported(format!(
+                "Unsupported code type '{}'",
+                code_type
+            )));
         }
+    };
+
+    // Structured codes pick their own dimension, which can still come out as zero
+    if g.dim().0 == 0 {
+        return Err(PqcatError::InvalidParameters(format!(
+            "{} code with n = {} has dimension 0, so it has no codewords to send",
+            code_type, n
+        )));
     }
-}
 
-pub fn generate_random_code(n: usize, k: usize) -> Result<(Array2<u8>, Array2<u8>), String> {
-    assert!(k < n, "k must be less than n");
-    let mut rng = rng();
-    let m = n - k; // Number of parity bits
+    if !is_orthogonal(&g, &h) {
+        return Err(PqcatError::GenerationFailed(format!(
+            "{} generator matrix is not orthogonal to the parity-check matrix",
+            code_type
+        )));
+    }
 
-    let p = Array2::from_shape_fn((k, m), |_| rng.random_range(0..=1)); // Generate a random (k x m) P matrix
+    Ok((g, h, key))
+}
 
-    let mut g = Array2::<u8>::zeros((k, n)); // Construct G = [I_k | P]
-    for i in 0..k {
-        g[(i, i)] = 1; // Identity part
-    }
-    for i in 0..k {
-        for j in 0..m {
-            g[(i, k + j)] = p[(i, j)];
-        }
+pub fn generate_random_code(
+    n: usize,
+    k: usize,
+    rng: &mut impl Rng,
+) -> Result<(BinaryMatrix, BinaryMatrix), PqcatError> {
+    if k >= n {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid random code parameters: k ({}) must be less than n ({})",
+            k, n
+        )));
     }
+    let m = n - k; // Number of parity bits
 
-    let mut h = Array2::<u8>::zeros((m, n)); // Construct H = [-P^T | I_m]
-    for i in 0..(m) {
-        h[(i, k + i)] = 1; // Identity part
-    }
+    let mut p = BinaryMatrix::zeros(k, m); // Generate a random (k x m) P matrix
     for i in 0..k {
         for j in 0..m {
-            h[(j, i)] = p[(i, j)]; // -P^T part (no need for negative in GF(2))
+            p.set(i, j, rng.random_range(0..=1));
         }
     }
 
+    let g = BinaryMatrix::hconcat(&[&BinaryMatrix::identity(k), &p]); // Construct G = [I_k | P]
+
+    // Construct H = [-P^T | I_m] (no need for negative in GF(2))
+    let h = BinaryMatrix::hconcat(&[&p.transpose(), &BinaryMatrix::identity(m)]);
+
     Ok((g, h))
 }
 
-pub fn generate_hamming_code(n: usize, k: usize) -> Result<(Array2<u8>, Array2<u8>), String> {
+pub fn generate_hamming_code(
+    n: usize,
+    k: usize,
+) -> Result<(BinaryMatrix, BinaryMatrix), PqcatError> {
+    // Columns are the numbers 1 to n in binary, so there are at most 2^m - 1 of them
+    if k >= n || n - k >= usize::BITS as usize || n >= 1 << (n - k) {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid Hamming code parameters: n ({}) must be at most 2^(n - k) - 1 with k ({}) < n",
+            n, k
+        )));
+    }
     let m = n - k; // Number of parity bits
 
-    let mut h = Array2::<u8>::zeros((m, n)); // Create parity-check matrix H (m x n)
+    let mut h = BinaryMatrix::zeros(m, n); // Create parity-check matrix H (m x n)
     for col in 0..n {
         let binary_repr = format!("{:0>width$b}", col + 1, width = m); // Columns are binary numbers (1 to n)
         for row in 0..m {
-            h[[row, col]] = binary_repr.as_bytes()[row] - b'0';
+            h.set(row, col, binary_repr.as_bytes()[row] - b'0');
         }
     }
 
-    // Rearrange H into systematic form [P^T | I_m]
-    let p_t = h.slice(s![.., ..k]).to_owned(); // Extract P^T (m x k)
-    let identity = Array2::<u8>::eye(m); // Identity matrix (m x m)
-    let mut systematic_h = Array2::<u8>::zeros((m, n));
-    systematic_h.slice_mut(s![.., ..k]).assign(&p_t); // Place P^T in the left part
-    systematic_h.slice_mut(s![.., k..]).assign(&identity); // Place I_m in the right part
-
-    // Compute generator matrix G (k x n) = [I_k | P]
-    let p = p_t.t().to_owned(); // Transpose of P^T (k x m)
-    let identity_k = Array2::<u8>::eye(k); // Identity matrix (k x k)
-    let g = ndarray::concatenate(Axis(1), &[identity_k.view(), p.view()]).unwrap(); // Concatenate I_k and P along columns
+    // Row reduce H into systematic form [P^T | I_m] and derive G = [I_k | P]
+    let systematic = convert_to_systematic(h);
 
-    Ok((g, systematic_h))
+    Ok((systematic.g, systematic.h))
 }
 
 pub fn generate_goppa_code(
     n: usize,
     k: usize,
     t: usize,
-) -> Result<(Array2<u8>, Array2<u8>, GoppaParams), String> {
+    kind: GoppaPolyKind,
+    field: GoppaField,
+    rng: &mut impl Rng,
+) -> Result<(BinaryMatrix, BinaryMatrix, GoppaParams), PqcatError> {
     let m = (n as f64).log2().ceil() as u8; // Determine the field size m such that 2^m > n
 
-    if k > n - (m as usize) * t {
-        return Err(format!(
-            "Invalid Goppa code parameters: k ({}) must be ≤ n - m*t ({} - {}*{} = {})",
-            k,
-            n,
-            m,
-            t,
-            n - (m as usize) * t
-        ));
+    if (m as usize) * t > n || k > n - (m as usize) * t {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid Goppa code parameters: k ({}) must be ≤ n - m*t ({} - {}*{})",
+            k, n, m, t
+        )));
     }
 
-    let (goppa_poly, support, field) = generate_valid_goppa_params(n, t);
+    let (goppa_poly, support, field) = generate_valid_goppa_params(n, t, kind, field, rng)?;
 
-    let h = generate_goppa_parity_matrix(n, t, &goppa_poly, &support, &field);
+    let h = generate_goppa_parity_matrix(n, t, &goppa_poly, &support, &field)?;
 
-    let (g, h_systematic) = convert_to_systematic(h); // Convert H to systematic form and derive the generator matrix
+    // Convert H to systematic form and derive the generator matrix
+    let systematic = convert_to_systematic(h);
+
+    // Columns were permuted, so reorder the support to keep position i tied to its field element
+    let support = systematic.permutation.iter().map(|&j| support[j]).collect();
 
     let params = GoppaParams {
         field,
@@ -134,64 +166,73 @@ pub fn generate_goppa_code(
         t,
     };
 
-    Ok((g, h_systematic, params))
+    Ok((systematic.g, systematic.h, params))
 }
 
-pub fn generate_qc_code(n: usize, k: usize) -> Result<(Array2<u8>, Array2<u8>), String> {
-    let r = n - k; // Number of parity bits
-
-    if n % r != 0 || k % r != 0 {
-        return Err(format!(
-            "Invalid QC code parameters: both n ({}) and k ({}) should be multiples of r ({})",
-            n, k, r
-        ));
+// QC-MDPC code in the style of BIKE: H = [H0 | H1 | ... | H_(l-1)] built from l random sparse
+// circulants of odd weight over a prime block size r = n - k, with the systematic generator
+// G = [(H0^-1 Hi)^T | I] computed in F2[x]/(x^r - 1)
+pub fn generate_qc_code(
+    n: usize,
+    k: usize,
+    circulant_weight: usize,
+    rng: &mut impl Rng,
+) -> Result<(BinaryMatrix, BinaryMatrix, QcMdpcKey), PqcatError> {
+    let r = n.saturating_sub(k); // Block size, one block row of circulants
+    if r == 0 || !n.is_multiple_of(r) || n / r < 2 {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid QC code parameters: n ({}) must be a multiple l >= 2 of r = n - k ({})",
+            n, r
+        )));
     }
-
-    let p = r; // Block size for circulant matrices (using r for simplicity)
-    let num_blocks_cols = n / p;
-    let num_blocks_rows = r / p;
-
-    if num_blocks_rows != 1 {
-        return Err(format!(
-            "For simplicity, this implementation requires r=p, got r={}, p={}",
-            r, p
-        ));
+    if !is_prime(r) {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid QC code parameters: r ({}) must be prime",
+            r
+        )));
     }
-
-    // Create the parity-check matrix composed of circulant blocks
-    let mut h = Array2::<u8>::zeros((r, n));
-
-    for block_col in 0..num_blocks_cols {
-        // For each block column, generate a random first row
-        let mut first_row = vec![0; p];
-
-        // Make it sparse for better error correction (typically 2-3 1s per row)
-        let ones_per_row = 2.min(p / 2);
-        let mut indices: Vec<usize> = (0..p).collect();
-        indices.shuffle(&mut rng());
-
-        for &idx in indices.iter().take(ones_per_row) {
-            first_row[idx] = 1;
-        }
-
-        // Fill the block with cyclic shifts of the first row
-        for row in 0..p {
-            for col in 0..p {
-                let shifted_col = (col + row) % p;
-                h[[row, block_col * p + col]] = first_row[shifted_col];
-            }
-        }
+    if circulant_weight.is_multiple_of(2) || circulant_weight >= r {
+        return Err(PqcatError::InvalidParameters(format!(
+            "Invalid QC code parameters: circulant weight ({}) must be odd and below r ({})",
+            circulant_weight, r
+        )));
     }
-
-    // Ensure the last block is invertible by making it the identity matrix
-    for i in 0..p {
-        for j in 0..p {
-            h[[i, (num_blocks_cols - 1) * p + j]] = if i == j { 1 } else { 0 };
-        }
+    let index = n / r;
+
+    // Odd weight makes H0 invertible whenever x^r - 1 = (x - 1) * (irreducible), otherwise redraw
+    let (h0, h0_inverse) = (0..MAX_KEY_ATTEMPTS)
+        .find_map(|_| {
+            let h0 = Circulant::random_sparse(r, circulant_weight, rng);
+            h0.inverse().map(|inverse| (h0, inverse))
+        })
+        .ok_or_else(|| {
+            PqcatError::GenerationFailed(format!(
+                "Could not find an invertible circulant of weight {}",
+                circulant_weight
+            ))
+        })?;
+
+    let mut h = BinaryMatrix::zeros(r, n);
+    let mut g = BinaryMatrix::zeros(k, n);
+    h0.write_block(&mut h, 0, 0);
+    let mut blocks = vec![h0];
+    for block in 1..index {
+        let hi = Circulant::random_sparse(r, circulant_weight, rng);
+        hi.write_block(&mut h, 0, block * r);
+
+        // Messages go into block i, the redundancy H0^-1 Hi m into block 0
+        let public_key = hi.mul(&h0_inverse);
+        let rows = (block - 1) * r;
+        public_key.transpose().write_block(&mut g, rows, 0);
+        Circulant::one(r).write_block(&mut g, rows, block * r);
+        blocks.push(hi);
     }
 
-    // Convert to systematic form and derive generator matrix
-    let (g, h_systematic) = convert_to_systematic(h);
+    let key = QcMdpcKey {
+        r,
+        circulant_weight,
+        blocks,
+    };
 
-    Ok((g, h_systematic))
+    Ok((g, h, key))
 }
@@ -0,0 +1,521 @@
+use crate::types::{BinaryMatrix, BinaryVector, Circulant};
+use rand::Rng;
+use rand::seq::index::sample;
+
+const KARATSUBA_THRESHOLD: usize = 16; // Words per operand below which schoolbook is faster
+
+//-------------------------------------------------------------
+// Polynomials over GF(2) as bit-packed words, coefficient i at bit i
+//-------------------------------------------------------------
+
+fn degree(p: &[u64]) -> Option<usize> {
+    p.iter()
+        .rposition(|&w| w != 0)
+        .map(|i| i * 64 + 63 - p[i].leading_zeros() as usize)
+}
+
+// dst += src * x^shift, growing dst as needed
+fn xor_shifted(dst: &mut Vec<u64>, src: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    let needed = src.len() + words + 1;
+    if dst.len() < needed {
+        dst.resize(needed, 0);
+    }
+    for (i, &w) in src.iter().enumerate() {
+        dst[i + words] ^= w << bits;
+        if bits != 0 {
+            dst[i + words + 1] ^= w >> (64 - bits);
+        }
+    }
+}
+
+// Carry-less 64 x 64 -> 128 bit product
+fn clmul(a: u64, b: u64) -> (u64, u64) {
+    let (mut lo, mut hi) = (0, 0);
+    let mut bits = b;
+    while bits != 0 {
+        let i = bits.trailing_zeros();
+        lo ^= a << i;
+        if i != 0 {
+            hi ^= a >> (64 - i);
+        }
+        bits &= bits - 1;
+    }
+    (lo, hi)
+}
+
+fn schoolbook(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut product = vec![0; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        if x == 0 {
+            continue;
+        }
+        for (j, &y) in b.iter().enumerate() {
+            let (lo, hi) = clmul(x, y);
+            product[i + j] ^= lo;
+            product[i + j + 1] ^= hi;
+        }
+    }
+    product
+}
+
+// a * b for operands of equal length n: with a = a0 + X a1 and b = b0 + X b1,
+// a * b = a0 b0 + X ((a0 + a1)(b0 + b1) + a0 b0 + a1 b1) + X^2 a1 b1, three half-size products
+fn karatsuba(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let n = a.len();
+    if n <= KARATSUBA_THRESHOLD {
+        return schoolbook(a, b);
+    }
+
+    let (low, high) = (n / 2, n - n / 2);
+    let pad = |half: &[u64]| {
+        let mut padded = half.to_vec();
+        padded.resize(high, 0);
+        padded
+    };
+    let (a0, b0) = (pad(&a[..low]), pad(&b[..low]));
+    let (a1, b1) = (&a[low..], &b[low..]);
+    let a_sum: Vec<u64> = a0.iter().zip(a1).map(|(x, y)| x ^ y).collect();
+    let b_sum: Vec<u64> = b0.iter().zip(b1).map(|(x, y)| x ^ y).collect();
+
+    let z0 = karatsuba(&a0, &b0);
+    let z2 = karatsuba(a1, b1);
+    let mut z1 = karatsuba(&a_sum, &b_sum);
+    for ((z, x), y) in z1.iter_mut().zip(&z0).zip(&z2) {
+        *z ^= x ^ y;
+    }
+
+    // The padded words only ever produce zeros, so anything past 2n can be dropped
+    let mut product = vec![0; 2 * n];
+    for (offset, z) in [(0, &z0), (low, &z1), (2 * low, &z2)] {
+        for (p, &w) in product[offset..].iter_mut().zip(z.iter()) {
+            *p ^= w;
+        }
+    }
+    product
+}
+
+// Fold a product of degree < 2r back into F2[x]/(x^r - 1) using x^r = 1
+fn reduce(mut product: Vec<u64>, r: usize) -> BinaryVector {
+    product.resize(2 * r.div_ceil(64) + 1, 0);
+    let full = BinaryVector {
+        len: product.len() * 64,
+        words: product,
+    };
+    let mut low = full.slice(0..r);
+    low.xor_assign(&full.slice(r..2 * r));
+    low
+}
+
+// Multiplicative order of 2 modulo an odd r
+fn order_of_two(r: usize) -> usize {
+    let mut power = 2 % r;
+    let mut order = 1;
+    while power != 1 % r {
+        power = power * 2 % r;
+        order += 1;
+    }
+    order
+}
+
+//-------------------------------------------------------------
+// Ring elements
+//-------------------------------------------------------------
+
+#[allow(dead_code)]
+impl Circulant {
+    pub fn zero(r: usize) -> Self {
+        Circulant::Sparse {
+            r,
+            support: Vec::new(),
+        }
+    }
+
+    pub fn one(r: usize) -> Self {
+        Circulant::Sparse {
+            r,
+            support: vec![0],
+        }
+    }
+
+    pub fn from_dense(bits: BinaryVector) -> Self {
+        Circulant::Dense(bits)
+    }
+
+    pub fn from_support(r: usize, mut support: Vec<usize>) -> Self {
+        support.sort_unstable();
+        Circulant::Sparse { r, support }
+    }
+
+    // Random element with exactly `weight` non-zero coefficients
+    pub fn random_sparse(r: usize, weight: usize, rng: &mut impl Rng) -> Self {
+        Circulant::from_support(r, sample(rng, r, weight).into_vec())
+    }
+
+    pub fn r(&self) -> usize {
+        match self {
+            Circulant::Dense(bits) => bits.len,
+            Circulant::Sparse { r, .. } => *r,
+        }
+    }
+
+    pub fn weight(&self) -> usize {
+        match self {
+            Circulant::Dense(bits) => bits.weight(),
+            Circulant::Sparse { support, .. } => support.len(),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.weight() == 0
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.support() == [0]
+    }
+
+    pub fn coeff(&self, i: usize) -> u8 {
+        match self {
+            Circulant::Dense(bits) => bits.get(i),
+            Circulant::Sparse { support, .. } => support.binary_search(&i).is_ok() as u8,
+        }
+    }
+
+    pub fn support(&self) -> Vec<usize> {
+        match self {
+            Circulant::Dense(bits) => bits.support(),
+            Circulant::Sparse { support, .. } => support.clone(),
+        }
+    }
+
+    pub fn to_dense(&self) -> BinaryVector {
+        match self {
+            Circulant::Dense(bits) => bits.clone(),
+            Circulant::Sparse { r, support } => BinaryVector::from_support(*r, support),
+        }
+    }
+
+    pub fn into_dense(self) -> Circulant {
+        Circulant::Dense(self.to_dense())
+    }
+
+    pub fn into_sparse(self) -> Circulant {
+        Circulant::Sparse {
+            r: self.r(),
+            support: self.support(),
+        }
+    }
+
+    pub fn add(&self, other: &Circulant) -> Circulant {
+        Circulant::Dense(self.to_dense().xor(&other.to_dense()))
+    }
+
+    // x^shift * a(x): the first row rotated right by `shift`
+    pub fn rotate(&self, shift: usize) -> Circulant {
+        let r = self.r();
+        let shift = shift % r;
+        match self {
+            Circulant::Sparse { support, .. } => {
+                Circulant::from_support(r, support.iter().map(|&i| (i + shift) % r).collect())
+            }
+            Circulant::Dense(bits) => {
+                let mut words = bits.slice(r - shift..r).words;
+                xor_shifted(&mut words, &bits.slice(0..r - shift).words, shift);
+                words.truncate(r.div_ceil(64));
+                Circulant::Dense(BinaryVector { len: r, words })
+            }
+        }
+    }
+
+    // a(x^-1), the transposed circulant matrix
+    pub fn transpose(&self) -> Circulant {
+        let r = self.r();
+        let support = self.support().iter().map(|&i| (r - i) % r).collect();
+        match self {
+            Circulant::Dense(_) => Circulant::from_support(r, support).into_dense(),
+            Circulant::Sparse { .. } => Circulant::from_support(r, support),
+        }
+    }
+
+    // a(x)^(2^k) = a(x^(2^k)) in characteristic 2, a mere permutation of the coefficients
+    pub fn square_times(&self, k: usize) -> Circulant {
+        let r = self.r();
+        let mut factor = 1;
+        for _ in 0..k {
+            factor = factor * 2 % r;
+        }
+        let mut result = BinaryVector::zeros(r);
+        for i in self.support() {
+            result.flip(i * factor % r);
+        }
+        match self {
+            Circulant::Dense(_) => Circulant::Dense(result),
+            Circulant::Sparse { .. } => Circulant::from_support(r, result.support()),
+        }
+    }
+
+    pub fn square(&self) -> Circulant {
+        self.square_times(1)
+    }
+
+    // Product in F2[x]/(x^r - 1), picking the algorithm from the two representations
+    pub fn mul(&self, other: &Circulant) -> Circulant {
+        match (self, other) {
+            (Circulant::Sparse { r, support: a }, Circulant::Sparse { support: b, .. }) => {
+                let mut product = BinaryVector::zeros(*r);
+                for &i in a {
+                    for &j in b {
+                        product.flip((i + j) % r);
+                    }
+                }
+                Circulant::from_support(*r, product.support())
+            }
+            (Circulant::Sparse { support, .. }, Circulant::Dense(dense))
+            | (Circulant::Dense(dense), Circulant::Sparse { support, .. }) => {
+                Circulant::Dense(Circulant::sparse_dense_product(support, dense))
+            }
+            (Circulant::Dense(a), Circulant::Dense(b)) => {
+                Circulant::Dense(reduce(karatsuba(&a.words, &b.words), a.len))
+            }
+        }
+    }
+
+    // Sum of the rotations of the dense operand, one per non-zero coefficient of the sparse one
+    fn sparse_dense_product(support: &[usize], dense: &BinaryVector) -> BinaryVector {
+        let rotating = Circulant::Dense(dense.clone());
+        let mut product = BinaryVector::zeros(dense.len);
+        for &i in support {
+            if let Circulant::Dense(rotated) = rotating.rotate(i) {
+                product.xor_assign(&rotated);
+            }
+        }
+        product
+    }
+
+    // Reference product on dense operands, quadratic in the number of words
+    pub fn mul_schoolbook(&self, other: &Circulant) -> Circulant {
+        let (a, b) = (self.to_dense(), other.to_dense());
+        Circulant::Dense(reduce(schoolbook(&a.words, &b.words), a.len))
+    }
+
+    // Inverse by the extended Euclidean algorithm against x^r - 1, None if not a unit.
+    // Long division is done one shift at a time, updating the Bezout coefficient alongside
+    pub fn inverse_ext_gcd(&self) -> Option<Circulant> {
+        let r = self.r();
+        let mut modulus = vec![0u64; r / 64 + 1];
+        modulus[0] = 1;
+        modulus[r / 64] |= 1 << (r % 64);
+
+        let (mut r0, mut r1) = (modulus, self.to_dense().words);
+        let (mut t0, mut t1) = (vec![0u64], vec![1u64]);
+        while let Some(d1) = degree(&r1) {
+            while let Some(d0) = degree(&r0)
+                && d0 >= d1
+            {
+                xor_shifted(&mut r0, &r1, d0 - d1);
+                xor_shifted(&mut t0, &t1, d0 - d1);
+            }
+            std::mem::swap(&mut r0, &mut r1);
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        if degree(&r0) != Some(0) {
+            return None;
+        }
+
+        // t0 may still exceed degree r, fold it back using x^r = 1
+        let t0 = BinaryVector {
+            len: t0.len() * 64,
+            words: t0,
+        };
+        let mut inverse = BinaryVector::zeros(r);
+        for i in t0.support() {
+            inverse.flip(i % r);
+        }
+        Some(Circulant::Dense(inverse))
+    }
+
+    // Itoh–Tsujii inversion for odd r: the unit group has exponent dividing 2^L - 1 with
+    // L = ord_r(2), so a^-1 = a^(2^L - 2) = (a^(2^(L-1) - 1))^2. The powers a^(2^k - 1) follow an
+    // addition chain on k where every a^(2^j) is a coefficient permutation, so only about
+    // log2(L) multiplications are needed
+    pub fn inverse_itoh_tsujii(&self) -> Option<Circulant> {
+        let r = self.r();
+        if r.is_multiple_of(2) || self.is_zero() {
+            return None;
+        }
+        let exponent = order_of_two(r) - 1;
+        if exponent == 0 {
+            return self.is_one().then(|| self.clone()); // r = 1: the ring is F2
+        }
+
+        // Square-and-multiply over the bits of the exponent, top down: with b = a^(2^k - 1),
+        // doubling gives b^(2^k) * b = a^(2^2k - 1) and an extra bit gives b^2 * a = a^(2^(k+1) - 1)
+        let base = self.clone().into_dense();
+        let mut power = base.clone();
+        let mut k = 1;
+        for bit in (0..usize::BITS - 1 - exponent.leading_zeros()).rev() {
+            power = power.square_times(k).mul(&power);
+            k *= 2;
+            if (exponent >> bit) & 1 == 1 {
+                power = power.square().mul(&base);
+                k += 1;
+            }
+        }
+
+        let inverse = power.square();
+        inverse.mul(self).is_one().then_some(inverse)
+    }
+
+    pub fn inverse(&self) -> Option<Circulant> {
+        if self.r().is_multiple_of(2) {
+            self.inverse_ext_gcd()
+        } else {
+            self.inverse_itoh_tsujii()
+        }
+    }
+
+    // Write the r x r circulant into `matrix` at (row, col): row i is x^i a(x)
+    pub fn write_block(&self, matrix: &mut BinaryMatrix, row: usize, col: usize) {
+        let r = self.r();
+        let support = self.support();
+        for i in 0..r {
+            for &j in &support {
+                matrix.set(row + i, col + (i + j) % r, 1);
+            }
+        }
+    }
+
+    // Dense r x r matrix view
+    pub fn to_matrix(&self) -> BinaryMatrix {
+        let mut matrix = BinaryMatrix::zeros(self.r(), self.r());
+        self.write_block(&mut matrix, 0, 0);
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    // Sizes above KARATSUBA_THRESHOLD words, with odd word counts so the halves are uneven.
+    // 2 is primitive modulo 1523 and 2053, but not modulo the prime 1153 or 4097 = 17 * 241
+    const SIZES: [usize; 4] = [1153, 1523, 2053, 4097];
+
+    fn random_dense(r: usize, rng: &mut StdRng) -> Circulant {
+        Circulant::random_sparse(r, r / 2, rng).into_dense()
+    }
+
+    // Cyclic convolution straight from the definition
+    fn naive_product(a: &Circulant, b: &Circulant) -> Vec<usize> {
+        let r = a.r();
+        let mut product = vec![0u8; r];
+        for i in a.support() {
+            for j in b.support() {
+                product[(i + j) % r] ^= 1;
+            }
+        }
+        (0..r).filter(|&i| product[i] == 1).collect()
+    }
+
+    #[test]
+    fn thresholds_are_exercised() {
+        assert!(SIZES.iter().all(|&r| r.div_ceil(64) > KARATSUBA_THRESHOLD));
+    }
+
+    #[test]
+    fn schoolbook_matches_the_definition() {
+        let mut rng = StdRng::seed_from_u64(30);
+        for r in [1, 2, 63, 64, 65, 200] {
+            let a = random_dense(r, &mut rng);
+            let b = Circulant::random_sparse(r, r.div_ceil(3), &mut rng);
+            assert_eq!(a.mul_schoolbook(&b).support(), naive_product(&a, &b));
+        }
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook_on_raw_words() {
+        let mut rng = StdRng::seed_from_u64(31);
+        for n in 1..=4 * KARATSUBA_THRESHOLD + 3 {
+            let a: Vec<u64> = (0..n).map(|_| rng.random()).collect();
+            let b: Vec<u64> = (0..n).map(|_| rng.random()).collect();
+            assert_eq!(karatsuba(&a, &b), schoolbook(&a, &b), "{} words", n);
+        }
+    }
+
+    #[test]
+    fn products_match_schoolbook() {
+        let mut rng = StdRng::seed_from_u64(32);
+        for r in SIZES {
+            let (a, b) = (random_dense(r, &mut rng), random_dense(r, &mut rng));
+            let sparse = Circulant::random_sparse(r, 71, &mut rng);
+            let other_sparse = Circulant::random_sparse(r, 45, &mut rng);
+            let pairs = [
+                (&a, &b),
+                (&a, &sparse),
+                (&sparse, &b),
+                (&sparse, &other_sparse),
+            ];
+            for (x, y) in pairs {
+                assert_eq!(
+                    x.mul(y).support(),
+                    x.mul_schoolbook(y).support(),
+                    "r = {}",
+                    r
+                );
+            }
+            assert_eq!(a.square().support(), a.mul_schoolbook(&a).support());
+        }
+    }
+
+    #[test]
+    fn inverses_agree_and_invert() {
+        let mut rng = StdRng::seed_from_u64(33);
+        for r in SIZES {
+            // Odd weights can be units, even weights are divisible by x + 1 and never are
+            for weight in [1, 71, (r / 2) | 1, 70] {
+                let a = Circulant::random_sparse(r, weight, &mut rng);
+                let by_gcd = a.inverse_ext_gcd();
+                let by_itoh_tsujii = a.inverse_itoh_tsujii();
+                assert_eq!(
+                    by_gcd.as_ref().map(Circulant::support),
+                    by_itoh_tsujii.as_ref().map(Circulant::support),
+                    "r = {}, weight = {}",
+                    r,
+                    weight
+                );
+                match by_gcd {
+                    Some(inverse) => assert!(a.mul_schoolbook(&inverse).is_one()),
+                    None => assert!(weight % 2 == 0 || r == 1153 || r == 4097),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn odd_weight_elements_are_units_when_two_is_primitive() {
+        let mut rng = StdRng::seed_from_u64(34);
+        for r in [1523, 2053] {
+            for _ in 0..5 {
+                let a = Circulant::random_sparse(r, 2 * rng.random_range(0..r / 2) + 1, &mut rng);
+                let inverse = a.inverse().expect("x^r - 1 = (x + 1) * irreducible");
+                assert!(inverse.mul(&a).is_one());
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_edge_cases() {
+        let r = 1523;
+        assert!(Circulant::one(r).inverse_itoh_tsujii().unwrap().is_one());
+        assert!(Circulant::one(r).inverse_ext_gcd().unwrap().is_one());
+        assert!(Circulant::zero(r).inverse_itoh_tsujii().is_none());
+        assert!(Circulant::zero(r).inverse_ext_gcd().is_none());
+
+        // Even r goes through the extended Euclidean algorithm only
+        let x = Circulant::from_support(2048, vec![1]);
+        assert!(x.inverse_itoh_tsujii().is_none());
+        assert_eq!(x.inverse().unwrap().support(), vec![2047]);
+    }
+}
@@ -1,27 +1,258 @@
-use ndarray::{Array2, Axis, s};
+use crate::types::{BinaryMatrix, BinaryVector, SystematicForm};
 
-pub fn convert_to_systematic(h: Array2<u8>) -> (Array2<u8>, Array2<u8>) {
+/// Gaussian elimination over GF(2), visiting candidate pivot columns in `column_order`
+///
+/// Stops after `max_pivots` pivots (or when the columns run out). Row i of the result has its
+/// pivot in column `pivots[i]`, which is zero in every other row. Rows from `pivots.len()` on
+/// are zero on all pivot columns. If a syndrome is given, the same row operations are applied to it.
+pub fn row_reduce(
+    h: &mut BinaryMatrix,
+    mut syndrome: Option<&mut BinaryVector>,
+    column_order: &[usize],
+    max_pivots: usize,
+) -> Vec<usize> {
+    let rows = h.rows;
+    let limit = max_pivots.min(rows);
+    let mut pivots = Vec::with_capacity(limit);
+
+    for &col in column_order {
+        if pivots.len() == limit {
+            break;
+        }
+        let rank = pivots.len();
+
+        // Find a row at or below the current rank with a one in this column
+        let Some(pivot_row) = (rank..rows).find(|&i| h.get(i, col) == 1) else {
+            continue;
+        };
+
+        h.swap_rows(rank, pivot_row);
+        if let Some(s) = syndrome.as_deref_mut() {
+            let (a, b) = (s.get(rank), s.get(pivot_row));
+            s.set(rank, b);
+            s.set(pivot_row, a);
+        }
+
+        // Clear the column in every other row
+        for i in 0..rows {
+            if i != rank && h.get(i, col) == 1 {
+                h.xor_rows(rank, i);
+                if let Some(s) = syndrome.as_deref_mut()
+                    && s.get(rank) == 1
+                {
+                    s.flip(i);
+                }
+            }
+        }
+
+        pivots.push(col);
+    }
+
+    pivots
+}
+
+/// Bring H to systematic form [P^T | I_(n-k)] and derive the matching G = [I_k | P]
+///
+/// The right-most columns are preferred as pivots, so an H that is already systematic keeps the
+/// identity permutation. Rank-deficient matrices are handled by dropping the dependent rows,
+/// in which case the returned dimension is larger than n - rows(H).
+pub fn convert_to_systematic(h: BinaryMatrix) -> SystematicForm {
     let (m, n) = h.dim();
-    let k = n - m;
+    let mut reduced = h;
+
+    // Prefer the last m columns as pivots, then fall back to the remaining ones
+    let tail_start = n.saturating_sub(m);
+    let column_order: Vec<usize> = (tail_start..n).chain(0..tail_start).collect();
+    let pivots = row_reduce(&mut reduced, None, &column_order, m);
+
+    let rank = pivots.len();
+    let k = n - rank;
+
+    // Information set first (in natural order), pivots last (in row order)
+    let mut is_pivot = vec![false; n];
+    for &col in &pivots {
+        is_pivot[col] = true;
+    }
+    let mut permutation: Vec<usize> = (0..n).filter(|&j| !is_pivot[j]).collect();
+    permutation.extend_from_slice(&pivots);
+
+    // Drop the zero rows left over by linearly dependent checks and reorder the columns
+    let systematic_h = reduced.slice(0..rank, 0..n).permute_columns(&permutation);
+
+    // Extract P^T (rank x k) from the left part of H and build G = [I_k | P]
+    let p = systematic_h.slice(0..rank, 0..k).transpose();
+    let identity_k = BinaryMatrix::identity(k);
+    let g = BinaryMatrix::hconcat(&[&identity_k, &p]);
+
+    SystematicForm {
+        g,
+        h: systematic_h,
+        permutation,
+        dimension: k,
+    }
+}
+
+/// Check that every row of G is a codeword of the code defined by H, i.e. G·H^T = 0
+pub fn is_orthogonal(g: &BinaryMatrix, h: &BinaryMatrix) -> bool {
+    g.cols == h.cols && g.mul(&h.transpose()).is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_generator::generate_qc_code;
+    use crate::codes::goppa::{generate_goppa_parity_matrix, generate_valid_goppa_params};
+    use crate::types::{GoppaField, GoppaPolyKind};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_matrix(rows: usize, cols: usize, rng: &mut StdRng) -> BinaryMatrix {
+        let mut matrix = BinaryMatrix::zeros(rows, cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                matrix.set(i, j, rng.random_range(0..=1));
+            }
+        }
+        matrix
+    }
+
+    // Column j is j + 1 in binary, most significant bit in row 0
+    fn hamming_parity_matrix(m: usize, n: usize) -> BinaryMatrix {
+        let mut h = BinaryMatrix::zeros(m, n);
+        for col in 0..n {
+            for row in 0..m {
+                h.set(row, col, ((col + 1) >> (m - 1 - row)) as u8 & 1);
+            }
+        }
+        h
+    }
+
+    // Rank by elimination on a basis indexed by leading bit, independent of row_reduce
+    fn rank(h: &BinaryMatrix) -> usize {
+        let mut basis: Vec<Option<BinaryVector>> = vec![None; h.cols];
+        let mut rank = 0;
+        for i in 0..h.rows {
+            let mut row = h.row_vector(i);
+            while let Some(&lead) = row.support().last() {
+                match &basis[lead] {
+                    Some(b) => row.xor_assign(b),
+                    None => {
+                        basis[lead] = Some(row);
+                        rank += 1;
+                        break;
+                    }
+                }
+            }
+        }
+        rank
+    }
+
+    fn check_systematic_form(h: &BinaryMatrix) -> SystematicForm {
+        let n = h.cols;
+        let rank = rank(h);
+        let form = convert_to_systematic(h.clone());
+        let k = form.dimension;
+
+        assert_eq!(k, n - rank);
+        assert_eq!(form.g.dim(), (k, n));
+        assert_eq!(form.h.dim(), (rank, n));
+        assert_eq!(form.g.slice(0..k, 0..k), BinaryMatrix::identity(k));
+        assert_eq!(form.h.slice(0..rank, k..n), BinaryMatrix::identity(rank));
+        assert!(is_orthogonal(&form.g, &form.h));
+
+        let mut sorted = form.permutation.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..n).collect::<Vec<_>>());
+
+        // Column i of G belongs to column permutation[i] of the input, so undo that
+        let mut inverse = vec![0; n];
+        for (i, &j) in form.permutation.iter().enumerate() {
+            inverse[j] = i;
+        }
+        let g = form.g.permute_columns(&inverse);
+        assert!(is_orthogonal(&g, h));
+        form
+    }
+
+    #[test]
+    fn random_parity_matrices() {
+        let mut rng = StdRng::seed_from_u64(40);
+        for (rows, n) in [(1, 2), (3, 7), (8, 20), (20, 40), (64, 130), (70, 140)] {
+            check_systematic_form(&random_matrix(rows, n, &mut rng));
+        }
+    }
+
+    #[test]
+    fn hamming_parity_matrices() {
+        for m in 2..=7 {
+            let n = (1 << m) - 1;
+            let form = check_systematic_form(&hamming_parity_matrix(m, n));
+            assert_eq!(form.dimension, n - m);
+        }
+    }
 
-    /*
-    In a textbook implementation, we'd perform Gaussian elimination to get H in systematic form
-    For simplicity, we'll assume H is already in a form where we can extract P
-    */
+    #[test]
+    fn goppa_parity_matrices() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let codes = [
+            (31, 3, GoppaField::Standard),
+            (60, 5, GoppaField::Standard),
+            (120, 6, GoppaField::Standard),
+            (2100, 4, GoppaField::ClassicMcEliece),
+        ];
+        for (n, t, field_choice) in codes {
+            let (poly, support, field) = generate_valid_goppa_params(
+                n,
+                t,
+                GoppaPolyKind::Irreducible,
+                field_choice,
+                &mut rng,
+            )
+            .unwrap();
+            if field_choice == GoppaField::ClassicMcEliece {
+                assert_eq!(field.poly, 0x1009);
+            }
+            let h = generate_goppa_parity_matrix(n, t, &poly, &support, &field).unwrap();
+            let form = check_systematic_form(&h);
+            assert!(form.dimension >= n - field.get_m() as usize * t);
+        }
+    }
 
-    // Extract P^T (m x k) from the left part of H
-    let p_t = h.slice(s![.., ..k]).to_owned();
+    #[test]
+    fn qc_parity_matrices() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for (n, k, weight) in [(46, 23, 5), (202, 101, 15), (309, 206, 11)] {
+            let (_, h, _) = generate_qc_code(n, k, weight, &mut rng).unwrap();
+            let form = check_systematic_form(&h);
+            assert_eq!(form.dimension, k);
+        }
+    }
 
-    // Create systematic form of H = [P^T | I_m]
-    let identity = Array2::<u8>::eye(m);
-    let mut systematic_h = Array2::<u8>::zeros((m, n));
-    systematic_h.slice_mut(s![.., ..k]).assign(&p_t);
-    systematic_h.slice_mut(s![.., k..]).assign(&identity);
+    #[test]
+    fn rank_deficient_parity_matrices() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let mut h = random_matrix(8, 16, &mut rng);
+        // Row 5 repeats row 1, row 6 sums rows 0 and 2, row 7 is zero
+        let (row1, row0, row2) = (h.row_vector(1), h.row_vector(0), h.row_vector(2));
+        h.set_row(5, &row1);
+        h.set_row(6, &row0.xor(&row2));
+        h.set_row(7, &BinaryVector::zeros(16));
+        let form = check_systematic_form(&h);
+        assert!(form.dimension >= 16 - 5);
+        assert!(form.dimension > 16 - h.rows);
 
-    // Create generator matrix G = [I_k | P]
-    let p = p_t.t().to_owned();
-    let identity_k = Array2::<u8>::eye(k);
-    let g = ndarray::concatenate(Axis(1), &[identity_k.view(), p.view()]).unwrap();
+        let form = check_systematic_form(&BinaryMatrix::zeros(4, 10));
+        assert_eq!(form.dimension, 10);
+        assert_eq!(form.permutation, (0..10).collect::<Vec<_>>());
+    }
 
-    (g, systematic_h)
+    #[test]
+    fn systematic_input_keeps_the_identity_permutation() {
+        let mut rng = StdRng::seed_from_u64(44);
+        let p_transpose = random_matrix(6, 10, &mut rng);
+        let h = BinaryMatrix::hconcat(&[&p_transpose, &BinaryMatrix::identity(6)]);
+        let form = check_systematic_form(&h);
+        assert_eq!(form.permutation, (0..16).collect::<Vec<_>>());
+        assert_eq!(form.h, h);
+    }
 }
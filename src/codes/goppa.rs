@@ -1,123 +1,95 @@
-use crate::codes::polynomial_utils::{evaluate_poly, random_irreducible_poly};
-use crate::types::FiniteField;
-use ndarray::Array2;
-use rand::rng;
+use crate::codes::polynomial_utils::{random_irreducible_poly, random_square_free_poly};
+use crate::types::{
+    AlternantParams, BinaryMatrix, FiniteField, GoppaField, GoppaParams, GoppaPolyKind, Poly,
+    PqcatError,
+};
+use rand::Rng;
 use rand::seq::SliceRandom;
 
-pub fn generate_valid_goppa_params(n: usize, t: usize) -> (Vec<u32>, Vec<u32>, FiniteField) {
-    let m = (n as f64).log2().ceil() as u8;
-    let field = FiniteField::new(m);
-
-    // Check if n is too close to maximum support size
-    let max_support_size = (1 << m) - 1; // 2^m - 1 (excluding zero)
+const MAX_POLY_ATTEMPTS: usize = 100; // Goppa polynomials tried before giving up on a support of size n
 
-    // Special case: For t=1, a full support is impossible (linear polynomial must have 1 root)
-    if t == 1 && n == max_support_size {
-        return generate_valid_goppa_params(max_support_size - 1, t);
-    }
+pub fn generate_valid_goppa_params(
+    n: usize,
+    t: usize,
+    kind: GoppaPolyKind,
+    field_choice: GoppaField,
+    rng: &mut impl Rng,
+) -> Result<(Poly, Vec<u32>, FiniteField), PqcatError> {
+    let m = (n as f64).log2().ceil() as u8;
+    let field = match field_choice {
+        GoppaField::Standard => FiniteField::new(m)?,
+        GoppaField::ClassicMcEliece => FiniteField::classic_mceliece(m)?,
+    };
 
+    // The support is drawn from the non-zero field elements that are not roots of g
+    let max_support_size = (1 << m) - 1;
     if n > max_support_size {
-        return generate_valid_goppa_params(max_support_size, t);
-    }
-
-    // For safety with nearly-full support (like n=63 in GF(2^6)), ensure our Goppa polynomial has minimal roots in the field
-    let mut best_poly = Vec::new();
-    let mut min_roots = max_support_size;
-
-    // Try more polynomials for larger field sizes or when we need a nearly-full support
-    let attempts = if n > max_support_size - 10 { 20 } else { 10 }; // Increased attempts
-
-    // Try multiple polynomials and choose the one with fewest roots
-    for _ in 0..attempts {
-        let poly = random_irreducible_poly(t, &field);
-        let mut root_count = 0;
-
-        for x in 1..(1 << m) {
-            if evaluate_poly(&poly, x as u32, &field) == 0 {
-                root_count += 1;
-            }
-        }
-
-        if root_count < min_roots {
-            min_roots = root_count;
-            best_poly = poly.clone();
-
-            // If we found a polynomial with few enough roots, use it
-            if root_count <= max_support_size - n {
-                break;
-            }
-        }
+        return Err(PqcatError::InvalidParameters(format!(
+            "n ({}) exceeds the {} non-zero elements of GF(2^{})",
+            n, max_support_size, m
+        )));
     }
 
-    // If our best polynomial still has too many roots to create a support of size n
-    if min_roots > max_support_size - n {
-        let adjusted_n = max_support_size - min_roots;
-        // If we can't create a support of reasonable size, try a different t value
-        if adjusted_n < n / 2 && t > 1 {
-            return generate_valid_goppa_params(n, t - 1);
-        }
-
-        return generate_valid_goppa_params(adjusted_n, t);
+    // A reducible g of degree at most 3 has a linear factor, so one non-zero element is its root
+    if kind == GoppaPolyKind::SquareFree && t <= 3 && n >= max_support_size {
+        return Err(PqcatError::InvalidParameters(format!(
+            "A reducible Goppa polynomial of degree {} has a root in GF(2^{}), so n ({}) must be below {}",
+            t, m, n, max_support_size
+        )));
     }
 
-    // Identify all non-roots to build our support from
-    let mut non_roots = Vec::with_capacity(max_support_size);
-    for x in 1..(1 << m) {
-        let x_byte = x as u32;
-        if evaluate_poly(&best_poly, x_byte, &field) != 0 {
-            non_roots.push(x_byte);
+    // An irreducible g of degree >= 2 has no roots in the field; a linear one (or a reducible one)
+    // may, so keep drawing until enough non-roots are left for the support
+    for _ in 0..MAX_POLY_ATTEMPTS {
+        let goppa_poly = match kind {
+            GoppaPolyKind::Irreducible => random_irreducible_poly(t, &field, rng)?,
+            GoppaPolyKind::SquareFree => random_square_free_poly(t, &field, rng)?,
+        };
+
+        let mut non_roots: Vec<u32> = (1..(1 << m) as u32)
+            .filter(|&x| goppa_poly.eval(x, &field) != 0)
+            .collect();
+
+        if non_roots.len() >= n {
+            // Shuffle the non-roots and take the first n elements as a random support
+            non_roots.shuffle(rng);
+            non_roots.truncate(n);
+            return Ok((goppa_poly, non_roots, field));
         }
     }
 
-    if non_roots.len() < n {
-        // Instead of panicking, adjust n to the number of non-roots we found
-        return generate_valid_goppa_params(non_roots.len(), t);
-    }
-
-    // Shuffle the non-roots to get a random support
-    let mut rng = rng();
-    non_roots.shuffle(&mut rng);
-
-    // Take the first n elements as our support
-    let valid_support = non_roots[0..n].to_vec();
-
-    (best_poly, valid_support, field)
+    Err(PqcatError::GenerationFailed(format!(
+        "Could not find a {:?} Goppa polynomial of degree {} leaving {} support elements in GF(2^{})",
+        kind, t, n, m
+    )))
 }
 
 pub fn generate_goppa_parity_matrix(
     n: usize,
     t: usize,
-    goppa_poly: &[u32],
+    goppa_poly: &Poly,
     support: &[u32],
     field: &FiniteField,
-) -> Array2<u8> {
+) -> Result<BinaryMatrix, PqcatError> {
     // Verify that support has enough elements
     if support.len() < n {
-        panic!(
+        return Err(PqcatError::InvalidParameters(format!(
             "Support vector too small: has {} elements but need {}",
             support.len(),
             n
-        );
+        )));
     }
 
     // The parity check matrix for a binary Goppa code has t*m rows
     let m = field.get_m() as usize;
-    let mut h = Array2::<u8>::zeros((t * m, n));
+    let mut h = BinaryMatrix::zeros(t * m, n);
 
-    for j in 0..n {
-        // For each support element L[j]
-        let l_j = support[j]; // This is safe now that we check support.len() >= n
-
-        // Calculate g(L[j])
-        let g_l_j = evaluate_poly(goppa_poly, l_j, field);
-
-        // Ensure g(L[j]) is not zero
-        if g_l_j == 0 {
-            panic!("Invalid support: g(L[{}])=0", j);
-        }
-
-        // Calculate 1/g(L[j])
-        let inv_g_l_j = field.inverse(g_l_j);
+    // For each support element L[j] (safe now that we check support.len() >= n)
+    for (j, &l_j) in support.iter().enumerate().take(n) {
+        // Calculate 1/g(L[j]), which only exists if g(L[j]) is not zero
+        let inv_g_l_j = field.inverse(goppa_poly.eval(l_j, field)).map_err(|_| {
+            PqcatError::InvalidParameters(format!("Invalid support: g(L[{}])=0", j))
+        })?;
 
         // Generate the column
         let mut power = 1u32; // Start with L[j]^0 = 1
@@ -127,7 +99,7 @@ pub fn generate_goppa_parity_matrix(
 
             // Convert to binary and place in the appropriate rows
             for bit in 0..m {
-                h[[i * m + bit, j]] = ((col_val >> bit) & 1) as u8;
+                h.set(i * m + bit, j, ((col_val >> bit) & 1) as u8);
             }
 
             // Calculate next power
@@ -135,5 +107,31 @@ pub fn generate_goppa_parity_matrix(
         }
     }
 
-    h
+    Ok(h)
+}
+
+impl AlternantParams {
+    // A binary Goppa code with square-free g equals the one built on g^2, which as an alternant
+    // code has multipliers 1/g(alpha)^2 and 2t syndromes, enough to correct all t errors. An error
+    // if g has a root on the support
+    pub fn from_goppa(goppa_params: &GoppaParams) -> Result<Self, PqcatError> {
+        let field = &goppa_params.field;
+        let multipliers = goppa_params
+            .support
+            .iter()
+            .map(|&alpha| {
+                field.inverse(field.field_square(goppa_params.goppa_poly.eval(alpha, field)))
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|_| {
+                PqcatError::InvalidParameters("g vanishes on a support element".to_string())
+            })?;
+
+        Ok(AlternantParams {
+            field: field.clone(),
+            support: goppa_params.support.clone(),
+            multipliers,
+            r: 2 * goppa_params.t,
+        })
+    }
 }
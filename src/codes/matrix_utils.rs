@@ -0,0 +1,470 @@
+use crate::types::{BinaryMatrix, BinaryVector};
+use ndarray::Array2;
+use std::ops::Range;
+
+const WORD_BITS: usize = 64;
+
+fn words_for(bits: usize) -> usize {
+    bits.div_ceil(WORD_BITS)
+}
+
+// Mask selecting the valid bits of the last word of a `bits`-long bit string
+fn tail_mask(bits: usize) -> u64 {
+    match bits % WORD_BITS {
+        0 => u64::MAX,
+        r => (1u64 << r) - 1,
+    }
+}
+
+/// Copies `len` bits starting at bit `start` of `src` into a fresh word vector
+fn extract_bits(src: &[u64], start: usize, len: usize) -> Vec<u64> {
+    let mut out = vec![0u64; words_for(len)];
+    let shift = start % WORD_BITS;
+    let first = start / WORD_BITS;
+
+    for (i, word) in out.iter_mut().enumerate() {
+        let lo = src.get(first + i).copied().unwrap_or(0);
+        *word = if shift == 0 {
+            lo
+        } else {
+            let hi = src.get(first + i + 1).copied().unwrap_or(0);
+            (lo >> shift) | (hi << (WORD_BITS - shift))
+        };
+    }
+
+    if let Some(last) = out.last_mut() {
+        *last &= tail_mask(len);
+    }
+
+    out
+}
+
+//-------------------------------------------------------------
+// Bit-packed vectors
+//-------------------------------------------------------------
+
+impl BinaryVector {
+    pub fn zeros(len: usize) -> Self {
+        BinaryVector {
+            len,
+            words: vec![0; words_for(len)],
+        }
+    }
+
+    /// Build from the one-byte-per-bit representation used by the CLI output
+    pub fn from_bits(bits: &[u8]) -> Self {
+        let mut v = Self::zeros(bits.len());
+        for (i, &b) in bits.iter().enumerate() {
+            if b & 1 == 1 {
+                v.words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+            }
+        }
+        v
+    }
+
+    /// Build a vector of length `len` with ones exactly at `positions`
+    pub fn from_support(len: usize, positions: &[usize]) -> Self {
+        let mut v = Self::zeros(len);
+        for &i in positions {
+            v.set(i, 1);
+        }
+        v
+    }
+
+    pub fn to_bits(&self) -> Vec<u8> {
+        (0..self.len).map(|i| self.get(i)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> u8 {
+        ((self.words[i / WORD_BITS] >> (i % WORD_BITS)) & 1) as u8
+    }
+
+    pub fn set(&mut self, i: usize, bit: u8) {
+        let mask = 1u64 << (i % WORD_BITS);
+        if bit & 1 == 1 {
+            self.words[i / WORD_BITS] |= mask;
+        } else {
+            self.words[i / WORD_BITS] &= !mask;
+        }
+    }
+
+    pub fn flip(&mut self, i: usize) {
+        self.words[i / WORD_BITS] ^= 1 << (i % WORD_BITS);
+    }
+
+    pub fn xor_assign(&mut self, other: &BinaryVector) {
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a ^= b;
+        }
+    }
+
+    pub fn xor(&self, other: &BinaryVector) -> BinaryVector {
+        let mut out = self.clone();
+        out.xor_assign(other);
+        out
+    }
+
+    /// Hamming weight (popcount)
+    pub fn weight(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Bits `range.start..range.end` as a new vector
+    pub fn slice(&self, range: Range<usize>) -> BinaryVector {
+        let len = range.end - range.start;
+        BinaryVector {
+            len,
+            words: extract_bits(&self.words, range.start, len),
+        }
+    }
+
+    /// Up to 64 bits starting at `start`, packed into a single word (handy as a hash/sort key)
+    pub fn window(&self, start: usize, len: usize) -> u64 {
+        debug_assert!(len <= WORD_BITS);
+        if len == 0 {
+            return 0;
+        }
+        extract_bits(&self.words, start, len)[0]
+    }
+
+    /// Indices of the set bits in increasing order
+    pub fn support(&self) -> Vec<usize> {
+        let mut positions = Vec::with_capacity(self.weight());
+        for (w, &word) in self.words.iter().enumerate() {
+            let mut bits = word;
+            while bits != 0 {
+                positions.push(w * WORD_BITS + bits.trailing_zeros() as usize);
+                bits &= bits - 1;
+            }
+        }
+        positions
+    }
+}
+
+//-------------------------------------------------------------
+// Bit-packed matrices
+//-------------------------------------------------------------
+
+impl BinaryMatrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        let words_per_row = words_for(cols);
+        BinaryMatrix {
+            rows,
+            cols,
+            words_per_row,
+            data: vec![0; rows * words_per_row],
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut m = Self::zeros(size, size);
+        for i in 0..size {
+            m.set(i, i, 1);
+        }
+        m
+    }
+
+    pub fn from_array2(a: &Array2<u8>) -> Self {
+        let (rows, cols) = a.dim();
+        let mut m = Self::zeros(rows, cols);
+        for ((i, j), &bit) in a.indexed_iter() {
+            if bit & 1 == 1 {
+                m.set(i, j, 1);
+            }
+        }
+        m
+    }
+
+    pub fn to_array2(&self) -> Array2<u8> {
+        Array2::from_shape_fn((self.rows, self.cols), |(i, j)| self.get(i, j))
+    }
+
+    pub fn dim(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> u8 {
+        ((self.data[row * self.words_per_row + col / WORD_BITS] >> (col % WORD_BITS)) & 1) as u8
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, bit: u8) {
+        let idx = row * self.words_per_row + col / WORD_BITS;
+        let mask = 1u64 << (col % WORD_BITS);
+        if bit & 1 == 1 {
+            self.data[idx] |= mask;
+        } else {
+            self.data[idx] &= !mask;
+        }
+    }
+
+    pub fn flip(&mut self, row: usize, col: usize) {
+        self.data[row * self.words_per_row + col / WORD_BITS] ^= 1 << (col % WORD_BITS);
+    }
+
+    /// Row-major view of a single row
+    pub fn row(&self, i: usize) -> &[u64] {
+        &self.data[i * self.words_per_row..(i + 1) * self.words_per_row]
+    }
+
+    pub fn row_mut(&mut self, i: usize) -> &mut [u64] {
+        &mut self.data[i * self.words_per_row..(i + 1) * self.words_per_row]
+    }
+
+    pub fn row_vector(&self, i: usize) -> BinaryVector {
+        BinaryVector {
+            len: self.cols,
+            words: self.row(i).to_vec(),
+        }
+    }
+
+    pub fn set_row(&mut self, i: usize, row: &BinaryVector) {
+        self.row_mut(i).copy_from_slice(&row.words);
+    }
+
+    /// Row `dst` ^= row `src`
+    pub fn xor_rows(&mut self, src: usize, dst: usize) {
+        if src == dst {
+            self.row_mut(dst).fill(0);
+            return;
+        }
+        let wpr = self.words_per_row;
+        let (s, d) = (src * wpr, dst * wpr);
+        for w in 0..wpr {
+            let value = self.data[s + w];
+            self.data[d + w] ^= value;
+        }
+    }
+
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let wpr = self.words_per_row;
+        for w in 0..wpr {
+            self.data.swap(a * wpr + w, b * wpr + w);
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|&w| w == 0)
+    }
+
+    /// Column-major view: row j of the result is column j of `self`
+    pub fn transpose(&self) -> BinaryMatrix {
+        let mut t = BinaryMatrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for (w, &word) in self.row(i).iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let j = w * WORD_BITS + bits.trailing_zeros() as usize;
+                    t.set(j, i, 1);
+                    bits &= bits - 1;
+                }
+            }
+        }
+        t
+    }
+
+    /// Sub-matrix made of the given row and column ranges
+    pub fn slice(&self, rows: Range<usize>, cols: Range<usize>) -> BinaryMatrix {
+        let width = cols.end - cols.start;
+        let mut out = BinaryMatrix::zeros(rows.end - rows.start, width);
+        for (dst, src) in rows.enumerate() {
+            let bits = extract_bits(self.row(src), cols.start, width);
+            out.row_mut(dst).copy_from_slice(&bits);
+        }
+        out
+    }
+
+    /// Place matrices with the same number of rows side by side
+    pub fn hconcat(blocks: &[&BinaryMatrix]) -> BinaryMatrix {
+        let rows = blocks.first().map_or(0, |b| b.rows);
+        let cols = blocks.iter().map(|b| b.cols).sum();
+        let mut out = BinaryMatrix::zeros(rows, cols);
+        let mut offset = 0;
+        for block in blocks {
+            assert_eq!(block.rows, rows, "hconcat needs equal row counts");
+            for i in 0..rows {
+                for (w, &word) in block.row(i).iter().enumerate() {
+                    let mut bits = word;
+                    while bits != 0 {
+                        let j = w * WORD_BITS + bits.trailing_zeros() as usize;
+                        out.set(i, offset + j, 1);
+                        bits &= bits - 1;
+                    }
+                }
+            }
+            offset += block.cols;
+        }
+        out
+    }
+
+    /// Matrix-vector product M·v^T (e.g. the syndrome H·e^T)
+    pub fn mul_vector(&self, v: &BinaryVector) -> BinaryVector {
+        let mut out = BinaryVector::zeros(self.rows);
+        for i in 0..self.rows {
+            let ones: u32 = self
+                .row(i)
+                .iter()
+                .zip(v.words.iter())
+                .map(|(a, b)| (a & b).count_ones())
+                .sum();
+            if ones & 1 == 1 {
+                out.set(i, 1);
+            }
+        }
+        out
+    }
+
+    /// Matrix product self·other
+    pub fn mul(&self, other: &BinaryMatrix) -> BinaryMatrix {
+        assert_eq!(self.cols, other.rows, "Dimension mismatch in product");
+        let mut out = BinaryMatrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for (w, &word) in self.row(i).iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let j = w * WORD_BITS + bits.trailing_zeros() as usize;
+                    let src = other.row(j);
+                    let dst = &mut out.data[i * out.words_per_row..(i + 1) * out.words_per_row];
+                    for (d, s) in dst.iter_mut().zip(src.iter()) {
+                        *d ^= s;
+                    }
+                    bits &= bits - 1;
+                }
+            }
+        }
+        out
+    }
+
+    /// New matrix whose column i is column `permutation[i]` of `self`
+    pub fn permute_columns(&self, permutation: &[usize]) -> BinaryMatrix {
+        let cols = self.transpose();
+        let mut permuted = BinaryMatrix::zeros(permutation.len(), self.rows);
+        for (i, &src) in permutation.iter().enumerate() {
+            permuted.row_mut(i).copy_from_slice(cols.row(src));
+        }
+        permuted.transpose()
+    }
+}
+
+impl From<&Array2<u8>> for BinaryMatrix {
+    fn from(a: &Array2<u8>) -> Self {
+        BinaryMatrix::from_array2(a)
+    }
+}
+
+impl From<&BinaryMatrix> for Array2<u8> {
+    fn from(m: &BinaryMatrix) -> Self {
+        m.to_array2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Axis, concatenate, s};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    // Widths around the word boundaries, where the packing can go wrong
+    const WIDTHS: [usize; 7] = [1, 37, 63, 64, 65, 100, 130];
+
+    fn random_array(rows: usize, cols: usize, rng: &mut StdRng) -> Array2<u8> {
+        Array2::from_shape_fn((rows, cols), |_| rng.random_range(0..=1))
+    }
+
+    #[test]
+    fn array2_conversions_round_trip() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for cols in WIDTHS {
+            let a = random_array(5, cols, &mut rng);
+            let m = BinaryMatrix::from(&a);
+            assert_eq!(m.dim(), (5, cols));
+            assert!((0..5).all(|i| (0..cols).all(|j| m.get(i, j) == a[[i, j]])));
+            assert_eq!(Array2::from(&m), a);
+        }
+    }
+
+    #[test]
+    fn slices_match_the_array_slices() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let a = random_array(20, 200, &mut rng);
+        let m = BinaryMatrix::from(&a);
+        for (start, end) in [(0, 1), (3, 67), (63, 65), (64, 200), (1, 130), (70, 70)] {
+            let expected = a.slice(s![4..17, start..end]).to_owned();
+            assert_eq!(m.slice(4..17, start..end).to_array2(), expected);
+
+            let row = m.row_vector(7).slice(start..end);
+            assert_eq!(row.to_bits(), a.slice(s![7, start..end]).to_vec());
+        }
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for cols in WIDTHS {
+            let a = random_array(70, cols, &mut rng);
+            let t = BinaryMatrix::from(&a).transpose();
+            assert_eq!(t.to_array2(), a.t().to_owned());
+            assert_eq!(t.transpose(), BinaryMatrix::from(&a));
+        }
+    }
+
+    #[test]
+    fn mul_matches_the_product_mod_2() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for inner in WIDTHS {
+            let a = random_array(9, inner, &mut rng);
+            let b = random_array(inner, 65, &mut rng);
+            let expected = Array2::from_shape_fn((9, 65), |(i, j)| {
+                (0..inner).fold(0, |acc, l| acc ^ (a[[i, l]] & b[[l, j]]))
+            });
+            let product = BinaryMatrix::from(&a).mul(&BinaryMatrix::from(&b));
+            assert_eq!(product.to_array2(), expected);
+        }
+    }
+
+    #[test]
+    fn permute_columns_moves_each_column() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for cols in WIDTHS {
+            let a = random_array(11, cols, &mut rng);
+            let mut permutation: Vec<usize> = (0..cols).collect();
+            permutation.shuffle(&mut rng);
+
+            let permuted = BinaryMatrix::from(&a).permute_columns(&permutation);
+            assert_eq!(permuted.dim(), (11, cols));
+            for (i, &src) in permutation.iter().enumerate() {
+                assert_eq!(permuted.to_array2().column(i), a.column(src));
+            }
+        }
+    }
+
+    #[test]
+    fn hconcat_places_blocks_side_by_side() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let blocks: Vec<Array2<u8>> = WIDTHS
+            .iter()
+            .map(|&cols| random_array(6, cols, &mut rng))
+            .collect();
+        let matrices: Vec<BinaryMatrix> = blocks.iter().map(BinaryMatrix::from).collect();
+
+        let joined = BinaryMatrix::hconcat(&matrices.iter().collect::<Vec<_>>());
+        let views: Vec<_> = blocks.iter().map(|b| b.view()).collect();
+        assert_eq!(joined.to_array2(), concatenate(Axis(1), &views).unwrap());
+    }
+}
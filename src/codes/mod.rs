@@ -1,3 +1,7 @@
+pub mod circulant;
 pub mod code_utils;
 pub mod goppa;
+pub mod matrix_utils;
 pub mod polynomial_utils;
+pub mod qc_mdpc;
+pub mod root_finding;
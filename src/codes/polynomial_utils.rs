@@ -1,47 +1,324 @@
-use crate::types::FiniteField;
-use rand::{Rng, rng};
+use crate::types::{FiniteField, Poly, PqcatError};
+use rand::Rng;
+
+pub const MAX_FIELD_DEGREE: u8 = 20; // Largest m whose log/antilog tables we are willing to build
+
+// Standard primitive polynomial for GF(2^m), lowest weight first
+fn default_field_polynomial(m: u8) -> Option<u32> {
+    let poly = match m {
+        2 => 0b111,       // x^2 + x + 1
+        3 => 0b1011,      // x^3 + x + 1
+        4 => 0b10011,     // x^4 + x + 1
+        5 => 0b100101,    // x^5 + x^2 + 1
+        6 => 0b1000011,   // x^6 + x + 1
+        7 => 0b10001001,  // x^7 + x^3 + 1
+        8 => 0b100011101, // x^8 + x^4 + x^3 + x^2 + 1
+        9 => 0x211,       // x^9 + x^4 + 1
+        10 => 0x409,      // x^10 + x^3 + 1
+        11 => 0x805,      // x^11 + x^2 + 1
+        12 => 0x1053,     // x^12 + x^6 + x^4 + x + 1
+        13 => 0x201b,     // x^13 + x^4 + x^3 + x + 1
+        14 => 0x4443,     // x^14 + x^10 + x^6 + x + 1
+        15 => 0x8003,     // x^15 + x + 1
+        16 => 0x1002d,    // x^16 + x^5 + x^3 + x^2 + 1
+        _ => return None,
+    };
+    Some(poly)
+}
+
+//-------------------------------------------------------------
+// Polynomials over GF(2) as bit patterns, used to validate field moduli
+//-------------------------------------------------------------
+
+fn bit_degree(a: u64) -> u32 {
+    63 - a.leading_zeros()
+}
+
+fn bit_mod(mut a: u64, b: u64) -> u64 {
+    let b_deg = bit_degree(b);
+    while a != 0 && bit_degree(a) >= b_deg {
+        a ^= b << (bit_degree(a) - b_deg);
+    }
+    a
+}
+
+fn bit_mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    let (mut a, mut b, mut result) = (bit_mod(a, modulus), b, 0);
+    while b != 0 {
+        if b & 1 == 1 {
+            result ^= a;
+        }
+        a = bit_mod(a << 1, modulus);
+        b >>= 1;
+    }
+    result
+}
+
+fn bit_pow_mod(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let (mut base, mut result) = (bit_mod(base, modulus), bit_mod(1, modulus));
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            result = bit_mul_mod(result, base, modulus);
+        }
+        base = bit_mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn bit_gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, bit_mod(a, b));
+    }
+    a
+}
+
+// Ben-Or over GF(2): f of degree m is irreducible iff gcd(x^(2^i) + x, f) = 1 for all i <= m/2
+pub fn is_irreducible_bit_poly(poly: u32) -> bool {
+    let f = poly as u64;
+    if f < 2 {
+        return false;
+    }
+    let m = bit_degree(f);
+    let mut x_power = 0b10;
+    for _ in 0..m / 2 {
+        x_power = bit_mul_mod(x_power, x_power, f);
+        if bit_gcd(f, x_power ^ 0b10) != 1 {
+            return false;
+        }
+    }
+    true
+}
+
+// f is primitive iff it is irreducible and x has order exactly 2^m - 1 modulo f,
+// i.e. x^((2^m - 1) / q) != 1 for every prime q dividing 2^m - 1
+pub fn is_primitive_bit_poly(poly: u32) -> bool {
+    if !is_irreducible_bit_poly(poly) {
+        return false;
+    }
+    let f = poly as u64;
+    let order = (1u64 << bit_degree(f)) - 1;
+    let mut remaining = order;
+    let mut q = 2;
+    while remaining > 1 {
+        if q * q > remaining {
+            q = remaining;
+        }
+        if remaining.is_multiple_of(q) {
+            if bit_pow_mod(0b10, order / q, f) == 1 {
+                return false;
+            }
+            while remaining.is_multiple_of(q) {
+                remaining /= q;
+            }
+        }
+        q += 1;
+    }
+    true
+}
+
+// Smallest primitive polynomial of degree m, found by exhaustive search over odd candidates
+pub fn find_primitive_polynomial(m: u8) -> Option<u32> {
+    if !(1..32).contains(&m) {
+        return None;
+    }
+    let top = 1u32 << m;
+    (1..top)
+        .step_by(2)
+        .map(|low| top | low)
+        .find(|&poly| is_primitive_bit_poly(poly))
+}
 
 impl FiniteField {
-    // Create a new finite field GF(2^m) with an irreducible polynomial
-    pub fn new(m: u8) -> Self {
-        assert!(m > 1 && m <= 16, "Field degree must be between 2 and 16");
-        // Choose a standard irreducible polynomial for common field sizes
-        let poly: u32 = match m {
-            2 => 0b111,       // x^2 + x + 1
-            3 => 0b1011,      // x^3 + x + 1
-            4 => 0b10011,     // x^4 + x + 1
-            5 => 0b100101,    // x^5 + x^2 + 1
-            6 => 0b1000011,   // x^6 + x + 1
-            7 => 0b10001001,  // x^7 + x^3 + 1
-            8 => 0b100011101, // x^8 + x^4 + x^3 + x^2 + 1
-            9 => 0x100000b,   // x^9 + x^5 + x^3 + x + 1
-            10 => 0x100003,   // x^10 + x^3 + x^2 + 1
-            11 => 0x105,      // x^11 + x^2 + 1
-            12 => 0x1053,     // x^12 + x^6 + x^4 + x + 1
-            13 => 0x201b,     // x^13 + x^9 + x^3 + x + 1
-            14 => 0x100b,     // x^14 + x^4 + x^3 + x + 1
-            15 => 0x100d,     // x^15 + x^5 + x^3 + x + 1
-            16 => 0x1002d,    // x^16 + x^12 + x^3 + x + 1
-            _ => panic!("Unsupported field size"),
+    // Create GF(2^m) with a primitive polynomial: the standard one for m <= 16, a searched one above
+    pub fn new(m: u8) -> Result<Self, PqcatError> {
+        if !(2..=MAX_FIELD_DEGREE).contains(&m) {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Field degree must be between 2 and {}, got {}",
+                MAX_FIELD_DEGREE, m
+            )));
+        }
+        let poly = default_field_polynomial(m)
+            .or_else(|| find_primitive_polynomial(m))
+            .ok_or_else(|| {
+                PqcatError::GenerationFailed(format!(
+                    "No primitive polynomial of degree {} found",
+                    m
+                ))
+            })?;
+        FiniteField::with_modulus(m, poly, true)
+    }
+
+    // Create GF(2^m) = GF(2)[x]/(poly), checking that poly has degree m and is irreducible.
+    // A primitive poly makes x itself the generator; otherwise another primitive element is searched
+    pub fn with_modulus(m: u8, poly: u32, require_primitive: bool) -> Result<Self, PqcatError> {
+        if !(2..=MAX_FIELD_DEGREE).contains(&m) {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Field degree must be between 2 and {}, got {}",
+                MAX_FIELD_DEGREE, m
+            )));
+        }
+        if bit_degree(poly as u64) != m as u32 {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Field polynomial {:#x} has degree {}, expected {}",
+                poly,
+                bit_degree(poly as u64),
+                m
+            )));
+        }
+        if !is_irreducible_bit_poly(poly) {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Field polynomial {:#x} is not irreducible",
+                poly
+            )));
+        }
+        if require_primitive && !is_primitive_bit_poly(poly) {
+            return Err(PqcatError::InvalidParameters(format!(
+                "Field polynomial {:#x} is not primitive",
+                poly
+            )));
+        }
+
+        let mut field = FiniteField {
+            m,
+            poly,
+            generator: 0,
+            exp_table: Vec::new(),
+            log_table: Vec::new(),
         };
+        field.build_tables();
+        Ok(field)
+    }
 
-        FiniteField { m, poly }
+    // The fields fixed by the Classic McEliece specification: z^12 + z^3 + 1 and z^13 + z^4 + z^3 + z + 1
+    pub fn classic_mceliece(m: u8) -> Result<Self, PqcatError> {
+        match m {
+            12 => FiniteField::with_modulus(12, 0x1009, false),
+            13 => FiniteField::with_modulus(13, 0x201b, false),
+            _ => Err(PqcatError::Unsupported(format!(
+                "Classic McEliece defines no field for m = {}",
+                m
+            ))),
+        }
+    }
+
+    // Find a primitive element and tabulate its powers, so that products become index additions.
+    // The modulus is already known to be irreducible, so a generator always exists
+    fn build_tables(&mut self) {
+        let order = self.multiplicative_order();
+        let generator = (2..=order as u32)
+            .find(|&g| self.element_order_reference(g) == Some(order))
+            .expect("GF(2^m)* is cyclic");
+
+        let mut exp_table = vec![0; 2 * order];
+        let mut log_table = vec![0; order + 1];
+        let mut power = 1;
+        for i in 0..order {
+            exp_table[i] = power;
+            exp_table[i + order] = power;
+            log_table[power as usize] = i as u32;
+            power = self.field_multiply_reference(power, generator);
+        }
+
+        self.generator = generator;
+        self.exp_table = exp_table;
+        self.log_table = log_table;
+    }
+
+    // Order of an element via the bit-level multiply, or None if its powers leave GF(2^m) or never
+    // reach 1. An element of order 2^m - 1 only exists when the polynomial is irreducible
+    fn element_order_reference(&self, g: u32) -> Option<usize> {
+        let mut power = g;
+        for i in 1..=self.multiplicative_order() {
+            if power == 1 {
+                return Some(i);
+            }
+            if power == 0 || power >> self.m != 0 {
+                return None;
+            }
+            power = self.field_multiply_reference(power, g);
+        }
+        None
     }
 
     pub fn get_m(&self) -> u8 {
         self.m
     }
 
+    // Size of the multiplicative group, 2^m - 1
+    pub fn multiplicative_order(&self) -> usize {
+        (1 << self.m) - 1
+    }
+
     // Addition in GF(2^m) is bitwise XOR
     pub fn field_add(&self, a: u32, b: u32) -> u32 {
         a ^ b
     }
 
-    // Multiplication in GF(2^m)
+    // Multiplication in GF(2^m): g^i * g^j = g^(i+j)
     pub fn field_multiply(&self, a: u32, b: u32) -> u32 {
         if a == 0 || b == 0 {
             return 0;
         }
+        self.exp_table[(self.log_table[a as usize] + self.log_table[b as usize]) as usize]
+    }
+
+    pub fn field_square(&self, a: u32) -> u32 {
+        if a == 0 {
+            return 0;
+        }
+        self.exp_table[2 * self.log_table[a as usize] as usize]
+    }
+
+    // a^e in GF(2^m), with 0^0 = 1
+    pub fn field_pow(&self, a: u32, e: u64) -> u32 {
+        if e == 0 {
+            return 1;
+        }
+        if a == 0 {
+            return 0;
+        }
+        let order = self.multiplicative_order() as u64;
+        let log = self.log_table[a as usize] as u64 * (e % order) % order;
+        self.exp_table[log as usize]
+    }
+
+    // Square root in GF(2^m): the group order 2^m - 1 is odd, so halve the log modulo it
+    pub fn field_sqrt(&self, a: u32) -> u32 {
+        if a == 0 {
+            return 0;
+        }
+        let log = self.log_table[a as usize] as usize;
+        let half = if log.is_multiple_of(2) {
+            log / 2
+        } else {
+            (log + self.multiplicative_order()) / 2
+        };
+        self.exp_table[half]
+    }
+
+    // Find the multiplicative inverse of an element in GF(2^m): g^-i = g^(2^m - 1 - i)
+    pub fn inverse(&self, a: u32) -> Result<u32, PqcatError> {
+        if a == 0 {
+            return Err(zero_inverse_error(self.m));
+        }
+        Ok(self.inverse_nonzero(a))
+    }
+
+    // Table inverse for callers that already know a != 0, e.g. a leading coefficient
+    fn inverse_nonzero(&self, a: u32) -> u32 {
+        self.exp_table[self.multiplicative_order() - self.log_table[a as usize] as usize]
+    }
+
+    //-------------------------------------------------------------
+    // Bit-level reference arithmetic, used to build and cross-check the tables
+    //-------------------------------------------------------------
+
+    // Shift-and-add multiplication in GF(2^m)
+    pub fn field_multiply_reference(&self, a: u32, b: u32) -> u32 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
 
         let mut result = 0u32;
         let mut a_temp = a;
@@ -52,22 +329,25 @@ impl FiniteField {
                 result ^= a_temp;
             }
 
-            // Check if the leading bit will be shifted out
-            let highest_bit_set = a_temp & (1 << self.m) != 0;
-
             a_temp <<= 1;
 
-            if highest_bit_set {
+            // Reduce as soon as the shift reaches degree m, so a_temp stays in GF(2^m)
+            if a_temp & (1 << self.m) != 0 {
                 a_temp ^= self.poly;
             }
 
-            a_temp &= (1 << (self.m + 1)) - 1; // Keep only relevant bits
             b_temp >>= 1;
         }
 
         result
     }
 
+    // Squaring is an automorphism of order m, so sqrt(a) = a^(2^(m-1))
+    #[allow(dead_code)]
+    pub fn field_sqrt_reference(&self, a: u32) -> u32 {
+        (1..self.m).fold(a, |acc, _| self.field_multiply_reference(acc, acc))
+    }
+
     // Helper functions for bit-level field operations
     fn bit_polynomial_multiply(&self, a: u32, b: u32) -> u32 {
         let mut result = 0u32;
@@ -114,7 +394,8 @@ impl FiniteField {
             return 0;
         }
 
-        while tmp >= b {
+        // Compare degrees, not values: a same-degree tmp smaller than b still needs one more step
+        while tmp != 0 {
             let tmp_deg = 31 - tmp.leading_zeros() as u8;
             if tmp_deg < b_deg {
                 break;
@@ -125,9 +406,12 @@ impl FiniteField {
         tmp
     }
 
-    // Find the multiplicative inverse of an element in GF(2^m)
-    pub fn inverse(&self, a: u32) -> u32 {
-        assert!(a != 0, "Cannot invert zero");
+    // Multiplicative inverse by the extended Euclidean algorithm on bit polynomials
+    #[allow(dead_code)]
+    pub fn inverse_reference(&self, a: u32) -> Result<u32, PqcatError> {
+        if a == 0 {
+            return Err(zero_inverse_error(self.m));
+        }
 
         // Using Extended Euclidean Algorithm for GF(2^m)
         let mut r0 = self.poly;
@@ -151,52 +435,799 @@ impl FiniteField {
             t1 = t2;
         }
 
-        t0
+        Ok(t0)
     }
 }
 
+fn zero_inverse_error(m: u8) -> PqcatError {
+    PqcatError::InvalidParameters(format!("0 has no inverse in GF(2^{})", m))
+}
+
 //-------------------------------------------------------------
 // Polynomial operations over finite fields
 //-------------------------------------------------------------
 
-/// Trims leading zeros from a polynomial
-pub fn trim_polynomial(poly: &mut Vec<u32>) {
-    while poly.len() > 1 && poly[poly.len() - 1] == 0 {
-        poly.pop();
+#[allow(dead_code)]
+impl Poly {
+    pub fn new(mut coeffs: Vec<u32>) -> Self {
+        while coeffs.last() == Some(&0) {
+            coeffs.pop();
+        }
+        Poly { coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Poly { coeffs: Vec::new() }
+    }
+
+    pub fn constant(c: u32) -> Self {
+        Poly::new(vec![c])
+    }
+
+    pub fn one() -> Self {
+        Poly::constant(1)
+    }
+
+    // c * z^degree
+    pub fn monomial(c: u32, degree: usize) -> Self {
+        let mut coeffs = vec![0; degree + 1];
+        coeffs[degree] = c;
+        Poly::new(coeffs)
+    }
+
+    // The polynomial z
+    pub fn z() -> Self {
+        Poly::monomial(1, 1)
+    }
+
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    pub fn coeff(&self, i: usize) -> u32 {
+        self.coeffs.get(i).copied().unwrap_or(0)
+    }
+
+    pub fn leading(&self) -> u32 {
+        self.coeffs.last().copied().unwrap_or(0)
+    }
+
+    // Evaluate at x with Horner's rule
+    pub fn eval(&self, x: u32, field: &FiniteField) -> u32 {
+        self.coeffs.iter().rev().fold(0, |acc, &c| {
+            field.field_add(field.field_multiply(acc, x), c)
+        })
+    }
+
+    // Addition and subtraction coincide in characteristic 2
+    pub fn add(&self, other: &Poly) -> Poly {
+        let mut coeffs = vec![0; self.coeffs.len().max(other.coeffs.len())];
+        for (i, c) in coeffs.iter_mut().enumerate() {
+            *c = self.coeff(i) ^ other.coeff(i);
+        }
+        Poly::new(coeffs)
+    }
+
+    pub fn scale(&self, c: u32, field: &FiniteField) -> Poly {
+        Poly::new(
+            self.coeffs
+                .iter()
+                .map(|&x| field.field_multiply(x, c))
+                .collect(),
+        )
+    }
+
+    pub fn mul(&self, other: &Poly, field: &FiniteField) -> Poly {
+        if self.is_zero() || other.is_zero() {
+            return Poly::zero();
+        }
+        let mut coeffs = vec![0; self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            if a == 0 {
+                continue;
+            }
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                coeffs[i + j] ^= field.field_multiply(a, b);
+            }
+        }
+        Poly::new(coeffs)
+    }
+
+    // Cross terms cancel in characteristic 2, so only the coefficients get squared
+    pub fn square(&self, field: &FiniteField) -> Poly {
+        let mut coeffs = vec![0; (2 * self.coeffs.len()).saturating_sub(1)];
+        for (i, &c) in self.coeffs.iter().enumerate() {
+            coeffs[2 * i] = field.field_square(c);
+        }
+        Poly::new(coeffs)
+    }
+
+    // Quotient and remainder of self / divisor
+    pub fn divmod(&self, divisor: &Poly, field: &FiniteField) -> Result<(Poly, Poly), PqcatError> {
+        if divisor.is_zero() {
+            return Err(PqcatError::InvalidParameters(
+                "Division by the zero polynomial".to_string(),
+            ));
+        }
+        Ok(self.divide(divisor, field))
+    }
+
+    // Long division; a zero divisor leaves all of self in the remainder, as reducing modulo the
+    // zero polynomial changes nothing
+    fn divide(&self, divisor: &Poly, field: &FiniteField) -> (Poly, Poly) {
+        let Some(divisor_degree) = divisor.degree() else {
+            return (Poly::zero(), self.clone());
+        };
+        let lead_inv = field.inverse_nonzero(divisor.leading());
+
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![0; self.coeffs.len().saturating_sub(divisor_degree)];
+
+        while remainder.len() > divisor_degree {
+            let top = remainder.len() - 1;
+            let factor = field.field_multiply(remainder[top], lead_inv);
+            let shift = top - divisor_degree;
+            quotient[shift] = factor;
+            for (i, &c) in divisor.coeffs.iter().enumerate() {
+                remainder[i + shift] ^= field.field_multiply(factor, c);
+            }
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+        }
+
+        (Poly::new(quotient), Poly::new(remainder))
+    }
+
+    // self mod modulus, which a zero modulus leaves unchanged
+    pub fn rem(&self, modulus: &Poly, field: &FiniteField) -> Poly {
+        self.divide(modulus, field).1
+    }
+
+    // Scale so that the leading coefficient is 1
+    pub fn monic(&self, field: &FiniteField) -> Poly {
+        if self.is_zero() {
+            return Poly::zero();
+        }
+        self.scale(field.inverse_nonzero(self.leading()), field)
+    }
+
+    // Monic greatest common divisor
+    pub fn gcd(&self, other: &Poly, field: &FiniteField) -> Poly {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let r = a.rem(&b, field);
+            (a, b) = (b, r);
+        }
+        a.monic(field)
+    }
+
+    // Extended Euclid: returns (d, s, t) with s * self + t * other = d and d monic
+    pub fn ext_gcd(&self, other: &Poly, field: &FiniteField) -> (Poly, Poly, Poly) {
+        let (mut r0, mut r1) = (self.clone(), other.clone());
+        let (mut s0, mut s1) = (Poly::one(), Poly::zero());
+        let (mut t0, mut t1) = (Poly::zero(), Poly::one());
+
+        while !r1.is_zero() {
+            let (q, r) = r0.divide(&r1, field);
+            let s2 = s0.add(&q.mul(&s1, field));
+            let t2 = t0.add(&q.mul(&t1, field));
+            (r0, r1) = (r1, r);
+            (s0, s1) = (s1, s2);
+            (t0, t1) = (t1, t2);
+        }
+
+        if r0.is_zero() {
+            return (r0, s0, t0);
+        }
+        let lead_inv = field.inverse_nonzero(r0.leading());
+        (
+            r0.scale(lead_inv, field),
+            s0.scale(lead_inv, field),
+            t0.scale(lead_inv, field),
+        )
+    }
+
+    // Inverse modulo `modulus`, None when the two are not coprime
+    pub fn inverse_mod(&self, modulus: &Poly, field: &FiniteField) -> Option<Poly> {
+        let (d, s, _) = self.rem(modulus, field).ext_gcd(modulus, field);
+        (d == Poly::one()).then(|| s.rem(modulus, field))
+    }
+
+    pub fn mul_mod(&self, other: &Poly, modulus: &Poly, field: &FiniteField) -> Poly {
+        self.mul(other, field).rem(modulus, field)
+    }
+
+    // self^exponent mod modulus by square-and-multiply
+    pub fn pow_mod(&self, exponent: u64, modulus: &Poly, field: &FiniteField) -> Poly {
+        let mut result = Poly::one().rem(modulus, field);
+        let mut base = self.rem(modulus, field);
+        let mut e = exponent;
+        while e > 0 {
+            if e & 1 == 1 {
+                result = result.mul_mod(&base, modulus, field);
+            }
+            base = base.mul_mod(&base, modulus, field);
+            e >>= 1;
+        }
+        result
+    }
+
+    // self(inner(z)) with Horner's rule
+    pub fn compose(&self, inner: &Poly, field: &FiniteField) -> Poly {
+        self.coeffs.iter().rev().fold(Poly::zero(), |acc, &c| {
+            acc.mul(inner, field).add(&Poly::constant(c))
+        })
+    }
+
+    // Formal derivative: i * c_i is c_i for odd i and 0 for even i over GF(2^m)
+    pub fn derivative(&self) -> Poly {
+        Poly::new(
+            self.coeffs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+                .collect(),
+        )
+    }
+
+    // Write self = P0(z)^2 + z * P1(z)^2 and return (P0, P1)
+    pub fn split_even_odd(&self, field: &FiniteField) -> (Poly, Poly) {
+        let even = self.coeffs.iter().step_by(2);
+        let odd = self.coeffs.iter().skip(1).step_by(2);
+        (
+            Poly::new(even.map(|&c| field.field_sqrt(c)).collect()),
+            Poly::new(odd.map(|&c| field.field_sqrt(c)).collect()),
+        )
+    }
+
+    // Square root modulo `modulus`: sqrt(P) = P0 + sqrt(z) * P1, with sqrt(z) = g0 * g1^-1 for g = g0^2 + z * g1^2.
+    // None when g1 is not invertible (g is then not square-free)
+    pub fn sqrt_mod(&self, modulus: &Poly, field: &FiniteField) -> Option<Poly> {
+        let (g0, g1) = modulus.split_even_odd(field);
+        let sqrt_z = g0.mul_mod(&g1.inverse_mod(modulus, field)?, modulus, field);
+        let (p0, p1) = self.rem(modulus, field).split_even_odd(field);
+        Some(p0.add(&sqrt_z.mul(&p1, field)).rem(modulus, field))
+    }
+
+    // Ben-Or's test: a degree-t polynomial over GF(q) is irreducible iff gcd(z^(q^i) - z, g) = 1
+    // for every i <= t/2, i.e. it has no factor of degree i. z^(q^i) comes from m squarings per step
+    pub fn is_irreducible(&self, field: &FiniteField) -> bool {
+        let t = match self.degree() {
+            Some(t) if t > 0 => t,
+            _ => return false, // Zero and the units are not irreducible
+        };
+
+        let z = Poly::z();
+        let mut power = z.rem(self, field);
+        for _ in 1..=t / 2 {
+            for _ in 0..field.get_m() {
+                power = power.square(field).rem(self, field);
+            }
+            if power.add(&z).gcd(self, field).degree() != Some(0) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // No repeated factors iff gcd(g, g') = 1; g' = 0 means g is a square in characteristic 2
+    pub fn is_square_free(&self, field: &FiniteField) -> bool {
+        let derivative = self.derivative();
+        !derivative.is_zero() && self.gcd(&derivative, field).degree() == Some(0)
     }
 }
 
-/// Evaluate a polynomial at a point in GF(2^m)
-pub fn evaluate_poly(poly: &[u32], x: u32, field: &FiniteField) -> u32 {
-    if x == 0 {
-        return poly.first().copied().unwrap_or(0);
+/// Random monic polynomial of degree t with a non-zero constant term (so 0 is never a root)
+pub fn random_monic_poly(t: usize, field: &FiniteField, rng: &mut impl Rng) -> Poly {
+    let mut coeffs = vec![0u32; t + 1];
+    coeffs[t] = 1;
+    for coefficient in coeffs.iter_mut().take(t) {
+        *coefficient = rng.random_range(0..(1 << field.get_m())) as u32;
     }
+    if coeffs[0] == 0 {
+        coeffs[0] = 1;
+    }
+
+    Poly::new(coeffs)
+}
 
-    let mut result = *poly.last().unwrap_or(&0);
-    for i in (0..poly.len() - 1).rev() {
-        result = field.field_add(field.field_multiply(result, x), poly[i]);
+/// Draw random monic polynomials until one of degree t is irreducible.
+/// About one in t candidates is irreducible, so this terminates quickly. Fails for t = 0, where the
+/// only monic polynomial is the unit
+pub fn random_irreducible_poly(
+    t: usize,
+    field: &FiniteField,
+    rng: &mut impl Rng,
+) -> Result<Poly, PqcatError> {
+    if t == 0 {
+        return Err(PqcatError::InvalidParameters(
+            "No irreducible polynomial of degree 0 exists".to_string(),
+        ));
+    }
+    loop {
+        let candidate = random_monic_poly(t, field, rng);
+        if candidate.is_irreducible(field) {
+            return Ok(candidate);
+        }
     }
+}
 
-    result
+/// Draw random monic polynomials until one of degree t is square-free but reducible.
+/// Fails for t = 1, where every polynomial is irreducible
+pub fn random_square_free_poly(
+    t: usize,
+    field: &FiniteField,
+    rng: &mut impl Rng,
+) -> Result<Poly, PqcatError> {
+    if t < 2 {
+        return Err(PqcatError::InvalidParameters(format!(
+            "No reducible polynomial of degree {} exists",
+            t
+        )));
+    }
+    loop {
+        let candidate = random_monic_poly(t, field, rng);
+        if candidate.is_square_free(field) && !candidate.is_irreducible(field) {
+            return Ok(candidate);
+        }
+    }
 }
 
-/// Generate a random irreducible polynomial of degree t
-pub fn random_irreducible_poly(t: usize, field: &FiniteField) -> Vec<u32> {
-    let mut rng = rng();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
 
-    // Create a monic polynomial (highest coefficient is 1)
-    let mut poly = vec![0u32; t + 1];
-    poly[t] = 1; // Make it monic
+    // Every field the crate can build, plus the non-primitive Classic McEliece modulus for m = 12
+    fn all_fields() -> Vec<FiniteField> {
+        let mut fields: Vec<FiniteField> = (2..=MAX_FIELD_DEGREE)
+            .map(|m| FiniteField::new(m).unwrap())
+            .collect();
+        fields.push(FiniteField::classic_mceliece(12).unwrap());
+        fields.push(FiniteField::classic_mceliece(13).unwrap());
+        fields
+    }
 
-    // Generate random coefficients for the other terms
-    for coefficient in poly.iter_mut().take(t) {
-        *coefficient = rng.random_range(0..(1 << field.get_m())) as u32;
+    // All elements for small fields, a fixed random sample with the extremes for large ones
+    fn sample_elements(field: &FiniteField, rng: &mut StdRng) -> Vec<u32> {
+        let order = field.multiplicative_order() as u32;
+        if order <= 1 << 13 {
+            return (0..=order).collect();
+        }
+        let mut elements = vec![0, 1, 2, order];
+        elements.extend((0..2000).map(|_| rng.random_range(0..=order)));
+        elements
+    }
+
+    fn pow_reference(field: &FiniteField, a: u32, mut e: u64) -> u32 {
+        let (mut result, mut base) = (1, a);
+        while e > 0 {
+            if e & 1 == 1 {
+                result = field.field_multiply_reference(result, base);
+            }
+            base = field.field_multiply_reference(base, base);
+            e >>= 1;
+        }
+        result
+    }
+
+    #[test]
+    fn table_multiply_matches_reference() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for field in all_fields() {
+            let elements = sample_elements(&field, &mut rng);
+            let step = elements.len().div_ceil(64);
+            for &a in &elements {
+                for &b in elements.iter().step_by(step) {
+                    assert_eq!(
+                        field.field_multiply(a, b),
+                        field.field_multiply_reference(a, b),
+                        "{} * {} in GF(2^{})",
+                        a,
+                        b,
+                        field.get_m()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn table_square_and_sqrt_match_reference() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for field in all_fields() {
+            for a in sample_elements(&field, &mut rng) {
+                let square = field.field_square(a);
+                assert_eq!(square, field.field_multiply_reference(a, a));
+                assert_eq!(field.field_sqrt(a), field.field_sqrt_reference(a));
+                assert_eq!(field.field_sqrt(square), a);
+            }
+        }
+    }
+
+    #[test]
+    fn table_inverse_matches_reference() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for field in all_fields() {
+            for a in sample_elements(&field, &mut rng)
+                .into_iter()
+                .filter(|&a| a != 0)
+            {
+                let inverse = field.inverse(a).unwrap();
+                assert_eq!(
+                    inverse,
+                    field.inverse_reference(a).unwrap(),
+                    "1/{} in GF(2^{})",
+                    a,
+                    field.get_m()
+                );
+                assert_eq!(field.field_multiply_reference(a, inverse), 1);
+            }
+            assert!(field.inverse(0).is_err());
+            assert!(field.inverse_reference(0).is_err());
+        }
+    }
+
+    #[test]
+    fn table_pow_matches_reference() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for field in all_fields() {
+            let order = field.multiplicative_order() as u64;
+            let exponents = [0, 1, 2, 3, order - 1, order, order + 1, 2 * order + 5];
+            for a in sample_elements(&field, &mut rng).into_iter().step_by(7) {
+                for e in exponents.into_iter().chain([rng.random::<u64>()]) {
+                    assert_eq!(
+                        field.field_pow(a, e),
+                        pow_reference(&field, a, e),
+                        "{}^{}",
+                        a,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    // Random polynomial of exactly the given degree, with an arbitrary non-zero leading coefficient
+    fn random_poly(degree: usize, field: &FiniteField, rng: &mut StdRng) -> Poly {
+        let lead = rng.random_range(1..=field.multiplicative_order() as u32);
+        let mut coeffs: Vec<u32> = (0..degree)
+            .map(|_| rng.random_range(0..=field.multiplicative_order() as u32))
+            .collect();
+        coeffs.push(lead);
+        Poly::new(coeffs)
+    }
+
+    fn poly_fields() -> Vec<FiniteField> {
+        vec![FiniteField::new(4).unwrap(), FiniteField::new(8).unwrap()]
+    }
+
+    #[test]
+    fn divmod_recombines() {
+        let mut rng = StdRng::seed_from_u64(10);
+        for field in poly_fields() {
+            for _ in 0..200 {
+                let a = random_poly(rng.random_range(0..12), &field, &mut rng);
+                let b = random_poly(rng.random_range(0..6), &field, &mut rng);
+                let (q, r) = a.divmod(&b, &field).unwrap();
+                assert_eq!(q.mul(&b, &field).add(&r), a);
+                assert!(r.degree().is_none_or(|d| d < b.degree().unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn divmod_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        let b = Poly::new(vec![3, 0, 1]);
+        assert_eq!(
+            Poly::zero().divmod(&b, &field).unwrap(),
+            (Poly::zero(), Poly::zero())
+        );
+
+        // A lower-degree dividend is its own remainder
+        let a = Poly::new(vec![5, 7]);
+        assert_eq!(a.divmod(&b, &field).unwrap(), (Poly::zero(), a.clone()));
+
+        // Dividing by a constant never leaves a remainder
+        let (q, r) = a.divmod(&Poly::constant(6), &field).unwrap();
+        assert_eq!(r, Poly::zero());
+        assert_eq!(q, a.scale(field.inverse(6).unwrap(), &field));
+    }
+
+    #[test]
+    fn division_by_zero_is_rejected() {
+        let field = FiniteField::new(4).unwrap();
+        let a = Poly::new(vec![2, 5, 9]);
+        assert!(matches!(
+            a.divmod(&Poly::zero(), &field),
+            Err(PqcatError::InvalidParameters(_))
+        ));
+        // Reducing modulo zero changes nothing
+        assert_eq!(a.rem(&Poly::zero(), &field), a);
+        assert_eq!(a.mul_mod(&a, &Poly::zero(), &field), a.square(&field));
+    }
+
+    #[test]
+    fn ext_gcd_gives_bezout_coefficients() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for field in poly_fields() {
+            for _ in 0..200 {
+                // A shared factor makes the gcd non-trivial half of the time
+                let common = random_poly(rng.random_range(0..4), &field, &mut rng);
+                let a = random_poly(rng.random_range(0..6), &field, &mut rng).mul(&common, &field);
+                let b = random_poly(rng.random_range(0..6), &field, &mut rng).mul(&common, &field);
+                let (d, s, t) = a.ext_gcd(&b, &field);
+                assert_eq!(s.mul(&a, &field).add(&t.mul(&b, &field)), d);
+                assert_eq!(d.leading(), 1);
+                assert!(a.rem(&d, &field).is_zero() && b.rem(&d, &field).is_zero());
+                assert!(d.rem(&common, &field).is_zero());
+                assert_eq!(d, a.gcd(&b, &field));
+            }
+        }
+    }
+
+    #[test]
+    fn ext_gcd_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        let a = Poly::new(vec![2, 5, 9]);
+
+        let (d, s, t) = Poly::zero().ext_gcd(&Poly::zero(), &field);
+        assert!(d.is_zero() && s == Poly::one() && t.is_zero());
+
+        let (d, s, t) = a.ext_gcd(&Poly::zero(), &field);
+        assert_eq!(d, a.monic(&field));
+        assert_eq!(s, Poly::constant(field.inverse(9).unwrap()));
+        assert!(t.is_zero());
+
+        let (d, s, t) = a.ext_gcd(&Poly::constant(7), &field);
+        assert_eq!(d, Poly::one());
+        assert!(s.is_zero());
+        assert_eq!(t, Poly::constant(field.inverse(7).unwrap()));
+    }
+
+    #[test]
+    fn inverse_mod_inverts_modulo_irreducible() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for field in poly_fields() {
+            let g = random_irreducible_poly(5, &field, &mut rng).unwrap();
+            for _ in 0..100 {
+                let a = random_poly(rng.random_range(0..9), &field, &mut rng);
+                if a.rem(&g, &field).is_zero() {
+                    continue;
+                }
+                let inverse = a.inverse_mod(&g, &field).unwrap();
+                assert!(inverse.degree().unwrap() < 5);
+                assert_eq!(a.mul_mod(&inverse, &g, &field), Poly::one());
+            }
+            assert_eq!(Poly::zero().inverse_mod(&g, &field), None);
+            assert_eq!(g.inverse_mod(&g, &field), None);
+            assert_eq!(
+                Poly::constant(3).inverse_mod(&g, &field),
+                Some(Poly::constant(field.inverse(3).unwrap()))
+            );
+        }
+    }
+
+    #[test]
+    fn inverse_mod_rejects_shared_factors() {
+        let field = FiniteField::new(4).unwrap();
+        let factor = Poly::new(vec![1, 1]);
+        let g = factor.mul(&Poly::new(vec![2, 0, 1]), &field);
+        let a = factor.mul(&Poly::new(vec![6, 1]), &field);
+        assert_eq!(a.inverse_mod(&g, &field), None);
     }
 
-    // Ensure the constant term is non-zero for irreducibility
-    if poly[0] == 0 {
-        poly[0] = 1;
+    #[test]
+    fn pow_mod_matches_repeated_multiplication() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for field in poly_fields() {
+            let modulus = random_poly(4, &field, &mut rng);
+            let a = random_poly(6, &field, &mut rng);
+            let mut expected = Poly::one();
+            for e in 0..40 {
+                assert_eq!(a.pow_mod(e, &modulus, &field), expected, "exponent {}", e);
+                expected = expected.mul_mod(&a, &modulus, &field);
+            }
+        }
+    }
+
+    #[test]
+    fn pow_mod_satisfies_fermat_for_irreducible_moduli() {
+        let mut rng = StdRng::seed_from_u64(14);
+        let field = FiniteField::new(4).unwrap();
+        let g = random_irreducible_poly(3, &field, &mut rng).unwrap();
+        // GF(16)[z]/(g) has 16^3 elements, so a^(16^3) = a
+        for _ in 0..20 {
+            let a = random_poly(2, &field, &mut rng);
+            assert_eq!(a.pow_mod(1 << 12, &g, &field), a);
+        }
+    }
+
+    #[test]
+    fn pow_mod_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        let modulus = Poly::new(vec![1, 1, 1]);
+        assert_eq!(Poly::zero().pow_mod(0, &modulus, &field), Poly::one());
+        assert_eq!(Poly::zero().pow_mod(5, &modulus, &field), Poly::zero());
+        assert_eq!(
+            Poly::constant(2).pow_mod(3, &modulus, &field),
+            Poly::constant(field.field_pow(2, 3))
+        );
+        // Everything is zero modulo a constant
+        let unit = Poly::constant(5);
+        assert_eq!(
+            Poly::new(vec![1, 2]).pow_mod(0, &unit, &field),
+            Poly::zero()
+        );
+    }
+
+    #[test]
+    fn compose_evaluates_like_nested_eval() {
+        let mut rng = StdRng::seed_from_u64(15);
+        for field in poly_fields() {
+            for _ in 0..50 {
+                let outer = random_poly(rng.random_range(0..6), &field, &mut rng);
+                let inner = random_poly(rng.random_range(0..4), &field, &mut rng);
+                let composed = outer.compose(&inner, &field);
+                for x in 0..16 {
+                    assert_eq!(
+                        composed.eval(x, &field),
+                        outer.eval(inner.eval(x, &field), &field)
+                    );
+                }
+            }
+        }
     }
 
-    poly
+    #[test]
+    fn compose_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        let p = Poly::new(vec![3, 0, 7, 1]);
+        assert_eq!(Poly::zero().compose(&p, &field), Poly::zero());
+        assert_eq!(Poly::constant(9).compose(&p, &field), Poly::constant(9));
+        assert_eq!(p.compose(&Poly::z(), &field), p);
+        assert_eq!(
+            p.compose(&Poly::constant(4), &field),
+            Poly::constant(p.eval(4, &field))
+        );
+        assert_eq!(p.compose(&Poly::zero(), &field), Poly::constant(3));
+    }
+
+    #[test]
+    fn derivative_follows_the_product_rule() {
+        let mut rng = StdRng::seed_from_u64(16);
+        for field in poly_fields() {
+            for _ in 0..100 {
+                let a = random_poly(rng.random_range(0..8), &field, &mut rng);
+                let b = random_poly(rng.random_range(0..8), &field, &mut rng);
+                let lhs = a.mul(&b, &field).derivative();
+                let rhs = a
+                    .derivative()
+                    .mul(&b, &field)
+                    .add(&a.mul(&b.derivative(), &field));
+                assert_eq!(lhs, rhs);
+            }
+        }
+    }
+
+    #[test]
+    fn derivative_edge_cases() {
+        assert_eq!(Poly::zero().derivative(), Poly::zero());
+        assert_eq!(Poly::constant(5).derivative(), Poly::zero());
+        assert_eq!(Poly::z().derivative(), Poly::one());
+        // Even powers vanish in characteristic 2
+        assert_eq!(Poly::monomial(7, 2).derivative(), Poly::zero());
+        assert_eq!(
+            Poly::new(vec![1, 2, 3, 4]).derivative(),
+            Poly::new(vec![2, 0, 4])
+        );
+    }
+
+    #[test]
+    fn sqrt_mod_squares_back() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for field in poly_fields() {
+            let irreducible = random_irreducible_poly(4, &field, &mut rng).unwrap();
+            let square_free = random_square_free_poly(4, &field, &mut rng).unwrap();
+            for g in [irreducible, square_free] {
+                for _ in 0..50 {
+                    let a = random_poly(rng.random_range(0..8), &field, &mut rng);
+                    let root = a.sqrt_mod(&g, &field).unwrap();
+                    assert!(root.degree().is_none_or(|d| d < 4));
+                    assert_eq!(root.mul_mod(&root, &g, &field), a.rem(&g, &field));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_mod_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        let g = Poly::new(vec![1, 1, 0, 1]);
+        assert_eq!(Poly::zero().sqrt_mod(&g, &field), Some(Poly::zero()));
+        assert_eq!(
+            Poly::constant(6).sqrt_mod(&g, &field),
+            Some(Poly::constant(field.field_sqrt(6)))
+        );
+        // z^2 + 1 = (z + 1)^2 has no odd part to invert
+        assert_eq!(Poly::z().sqrt_mod(&Poly::new(vec![1, 0, 1]), &field), None);
+    }
+
+    #[test]
+    fn with_modulus_validates_the_polynomial() {
+        let invalid = |m, poly, require_primitive| {
+            matches!(
+                FiniteField::with_modulus(m, poly, require_primitive),
+                Err(PqcatError::InvalidParameters(_))
+            )
+        };
+
+        // x^4 + x + 1 is primitive, so it passes either way
+        assert!(FiniteField::with_modulus(4, 0b10011, true).is_ok());
+        // x^4 + x^2 + 1 = (x^2 + x + 1)^2 is reducible
+        assert!(invalid(4, 0b10101, false));
+        // x^4 + x^3 + x^2 + x + 1 is irreducible, but x has order 5 modulo it
+        assert!(FiniteField::with_modulus(4, 0b11111, false).is_ok());
+        assert!(invalid(4, 0b11111, true));
+        // So is the Classic McEliece modulus x^12 + x^3 + 1
+        assert!(FiniteField::with_modulus(12, 0x1009, false).is_ok());
+        assert!(invalid(12, 0x1009, true));
+        // The degree has to match m, which has to be in the supported range
+        assert!(invalid(5, 0b10011, false));
+        assert!(invalid(3, 0b10011, false));
+        assert!(invalid(1, 0b11, false));
+        assert!(invalid(
+            MAX_FIELD_DEGREE + 1,
+            1 << (MAX_FIELD_DEGREE + 1) | 1,
+            false
+        ));
+    }
+
+    #[test]
+    fn is_irreducible_on_known_polynomials() {
+        let field = FiniteField::new(4).unwrap();
+        let poly = |coeffs: &[u32]| Poly::new(coeffs.to_vec());
+
+        // Irreducible over GF(2) and of degree coprime to 4, so still irreducible over GF(2^4)
+        assert!(poly(&[1, 1, 0, 1]).is_irreducible(&field));
+        assert!(poly(&[1, 0, 1, 0, 0, 1]).is_irreducible(&field));
+        assert!(poly(&[7, 1]).is_irreducible(&field));
+        // z^2 + z + 1 has its roots in GF(4), a subfield of GF(2^4) but not of GF(2^5)
+        assert!(!poly(&[1, 1, 1]).is_irreducible(&field));
+        assert!(poly(&[1, 1, 1]).is_irreducible(&FiniteField::new(5).unwrap()));
+        // z^4 + z + 1 defines GF(2^4), so it splits into linear factors there
+        assert!(!poly(&[1, 1, 0, 0, 1]).is_irreducible(&field));
+
+        // z^2 + z + c is irreducible exactly when the trace of c is 1
+        let trace = |c: u32| (0..4).fold(0, |acc, i| acc ^ field.field_pow(c, 1 << i));
+        for c in 1..16 {
+            assert_eq!(
+                poly(&[c, 1, 1]).is_irreducible(&field),
+                trace(c) == 1,
+                "c = {}",
+                c
+            );
+        }
+
+        // A product of two irreducible quadratics has no root but still factors
+        let mut traced = (1..16).filter(|&c| trace(c) == 1);
+        let (c1, c2) = (traced.next().unwrap(), traced.next().unwrap());
+        let product = poly(&[c1, 1, 1]).mul(&poly(&[c2, 1, 1]), &field);
+        assert!((0..16).all(|x| product.eval(x, &field) != 0));
+        assert!(!product.is_irreducible(&field));
+
+        // Squares, zero and the constants are not irreducible either
+        assert!(!poly(&[3, 1]).square(&field).is_irreducible(&field));
+        assert!(!Poly::zero().is_irreducible(&field));
+        assert!(!Poly::constant(5).is_irreducible(&field));
+    }
 }
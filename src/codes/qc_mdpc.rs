@@ -0,0 +1,71 @@
+pub const MAX_KEY_ATTEMPTS: usize = 100; // Draws of H0 before giving up on finding an invertible one
+
+pub fn is_prime(r: usize) -> bool {
+    r >= 2
+        && (2..)
+            .take_while(|d| d * d <= r)
+            .all(|d| !r.is_multiple_of(d))
+}
+
+// BIKE picks r such that 2 is primitive modulo r: x^r - 1 is then (x - 1) times a single
+// irreducible factor, so every odd-weight circulant is invertible and the ring has no small subrings
+pub fn is_primitive_two(r: usize) -> bool {
+    if !is_prime(r) || r == 2 {
+        return false;
+    }
+    let mut power = 1;
+    for _ in 1..r - 1 {
+        power = power * 2 % r;
+        if power == 1 {
+            return false;
+        }
+    }
+    true
+}
+
+// Odd circulant weight d scaled like BIKE (d = 71 for r = 12323), at least 3
+pub fn default_circulant_weight(r: usize) -> usize {
+    let d = (0.64 * (r as f64).sqrt()).round() as usize;
+    (d | 1).max(3)
+}
+
+// Smallest BIKE-style block size at or after r, see is_primitive_two
+pub fn next_bike_prime(r: usize) -> usize {
+    (r.max(3)..).find(|&p| is_primitive_two(p)).unwrap()
+}
+
+// The first BIKE-style prime at or after each of `points` evenly spread values in [from, to],
+// deduplicated, for block size sweeps
+pub fn prime_block_sizes(from: usize, to: usize, points: usize) -> Vec<usize> {
+    let step = (to.saturating_sub(from) / points.saturating_sub(1).max(1)).max(1);
+    let mut sizes: Vec<usize> = (0..points)
+        .map(|i| next_bike_prime(from + i * step))
+        .collect();
+    sizes.dedup();
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bike_block_sizes_have_two_primitive() {
+        // The block sizes of BIKE levels 1, 3 and 5 and the ones the real-world configs use
+        for r in [12323, 24659, 40973, 4093, 8179] {
+            assert!(is_primitive_two(r), "r = {}", r);
+        }
+        // 2^13 - 1 is prime but 2 has order 13 modulo it; 9 and 2 are not odd primes
+        for r in [8191, 7, 9, 2, 1, 0] {
+            assert!(!is_primitive_two(r), "r = {}", r);
+        }
+    }
+
+    #[test]
+    fn block_size_sweeps_only_pick_bike_primes() {
+        let sizes = prime_block_sizes(450, 650, 5);
+        assert!(!sizes.is_empty());
+        assert!(sizes.iter().all(|&r| is_primitive_two(r) && r >= 450));
+        assert_eq!(next_bike_prime(8186), 8219);
+    }
+}
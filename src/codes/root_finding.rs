@@ -0,0 +1,339 @@
+use crate::types::{FiniteField, Poly, RootFinder};
+
+//-------------------------------------------------------------
+// Gao–Mateer additive FFT
+//-------------------------------------------------------------
+
+// Taylor expansion at z^2 + z, in place: afterwards coeffs[2i] + coeffs[2i+1] z is the i-th
+// coefficient of f = sum_i (g_i0 + g_i1 z) (z^2 + z)^i
+fn taylor_expand(coeffs: &mut [u32]) {
+    let n = coeffs.len();
+    if n <= 2 {
+        return;
+    }
+
+    // Divide by (z^2 + z)^a = z^2a + z^a for the largest power a with 2a < n, so both the
+    // quotient and the remainder have at most 2a coefficients and expand independently
+    let mut a = 1;
+    while 4 * a < n {
+        a *= 2;
+    }
+    for i in (2 * a..n).rev() {
+        coeffs[i - a] ^= coeffs[i];
+    }
+
+    let (remainder, quotient) = coeffs.split_at_mut(2 * a);
+    taylor_expand(remainder);
+    taylor_expand(quotient);
+}
+
+// All sums of subsets of the basis, indexed so that bit j of the index selects basis[j]
+fn span(basis: &[u32]) -> Vec<u32> {
+    let mut points = vec![0; 1 << basis.len()];
+    for i in 1..points.len() {
+        points[i] = points[i & (i - 1)] ^ basis[i.trailing_zeros() as usize];
+    }
+    points
+}
+
+fn fft(coeffs: &[u32], basis: &[u32], field: &FiniteField) -> Vec<u32> {
+    let size = 1 << basis.len();
+    if coeffs.len() <= 1 {
+        return vec![coeffs.first().copied().unwrap_or(0); size];
+    }
+    let Some((&last, rest)) = basis.split_last() else {
+        return vec![coeffs[0]];
+    };
+
+    // g(z) = f(last * z) is evaluated on the span of basis / last, which contains 1
+    let mut scaled: Vec<u32> = coeffs
+        .iter()
+        .scan(1, |power, &c| {
+            let term = field.field_multiply(c, *power);
+            *power = field.field_multiply(*power, last);
+            Some(term)
+        })
+        .collect();
+    taylor_expand(&mut scaled);
+    let even: Vec<u32> = scaled.iter().step_by(2).copied().collect();
+    let odd: Vec<u32> = scaled.iter().skip(1).step_by(2).copied().collect();
+
+    // g(x) = g0(x^2 + x) + x g1(x^2 + x) and x^2 + x is linear with kernel {0, 1},
+    // so g0 and g1 only need the half-size span of the images gamma^2 + gamma. last is non-zero
+    // in an independent basis, so last^(2^m - 2) is its inverse
+    let inverse = field.field_pow(last, field.multiplicative_order() as u64 - 1);
+    let gamma: Vec<u32> = rest
+        .iter()
+        .map(|&b| field.field_multiply(b, inverse))
+        .collect();
+    let delta: Vec<u32> = gamma.iter().map(|&g| field.field_square(g) ^ g).collect();
+    let even_values = fft(&even, &delta, field);
+    let odd_values = fft(&odd, &delta, field);
+
+    let half = size / 2;
+    let mut values = vec![0; size];
+    for (i, &point) in span(&gamma).iter().enumerate() {
+        let value = even_values[i] ^ field.field_multiply(point, odd_values[i]);
+        values[i] = value;
+        values[i + half] = value ^ odd_values[i];
+    }
+    values
+}
+
+// Evaluate f at every point of the span of a linearly independent basis (see `span` for the order)
+pub fn additive_fft(poly: &Poly, basis: &[u32], field: &FiniteField) -> Vec<u32> {
+    fft(&poly.coeffs, basis, field)
+}
+
+// Evaluate f at every element of GF(2^m): with the basis 1, 2, 4, ... entry a holds f(a)
+pub fn evaluate_everywhere(poly: &Poly, field: &FiniteField) -> Vec<u32> {
+    let basis: Vec<u32> = (0..field.get_m()).map(|j| 1 << j).collect();
+    additive_fft(poly, &basis, field)
+}
+
+//-------------------------------------------------------------
+// Berlekamp trace algorithm
+//-------------------------------------------------------------
+
+// Tr(beta z) mod f = sum over j < m of (beta z)^(2^j) mod f
+fn trace_mod(beta: u32, modulus: &Poly, field: &FiniteField) -> Poly {
+    let mut term = Poly::monomial(beta, 1).rem(modulus, field);
+    let mut trace = term.clone();
+    for _ in 1..field.get_m() {
+        term = term.square(field).rem(modulus, field);
+        trace = trace.add(&term);
+    }
+    trace
+}
+
+// Split a monic product of distinct linear factors with gcd(f, Tr(beta z)), trying basis elements
+// from `next` onwards. Tr(beta z) is 0 or 1 on every root, so each gcd separates some of them
+fn split_roots(poly: Poly, basis: &[u32], next: usize, field: &FiniteField, roots: &mut Vec<u32>) {
+    match poly.degree() {
+        None | Some(0) => return,
+        Some(1) => {
+            roots.push(poly.coeff(0)); // z + a over GF(2^m) has the root a
+            return;
+        }
+        _ => {}
+    }
+    for (j, &beta) in basis.iter().enumerate().skip(next) {
+        let factor = poly.gcd(&trace_mod(beta, &poly, field), field);
+        if factor
+            .degree()
+            .is_some_and(|d| d > 0 && Some(d) < poly.degree())
+            && let Ok((cofactor, _)) = poly.divmod(&factor, field)
+        {
+            split_roots(factor, basis, j + 1, field, roots);
+            split_roots(cofactor.monic(field), basis, j + 1, field, roots);
+            return;
+        }
+    }
+}
+
+// Distinct roots of f in GF(2^m), found by factoring rather than by evaluation
+pub fn berlekamp_trace_roots(poly: &Poly, field: &FiniteField) -> Vec<u32> {
+    if poly.degree().is_none_or(|d| d == 0) {
+        return Vec::new();
+    }
+
+    // gcd(f, z^(2^m) + z) keeps exactly one linear factor per root and drops everything else
+    let f = poly.monic(field);
+    let mut frobenius = Poly::z().rem(&f, field);
+    for _ in 0..field.get_m() {
+        frobenius = frobenius.square(field).rem(&f, field);
+    }
+    let linear_part = f.gcd(&frobenius.add(&Poly::z()), field);
+
+    let basis: Vec<u32> = (0..field.get_m()).map(|j| 1 << j).collect();
+    let mut roots = Vec::new();
+    split_roots(linear_part, &basis, 0, field, &mut roots);
+    roots.sort_unstable();
+    roots
+}
+
+//-------------------------------------------------------------
+// Roots over a code support
+//-------------------------------------------------------------
+
+// Positions whose support element is a root of f, either evaluating f on the whole field at once
+// or factoring out its roots and looking them up
+pub fn find_support_roots(
+    poly: &Poly,
+    support: &[u32],
+    field: &FiniteField,
+    root_finder: RootFinder,
+) -> Vec<usize> {
+    let is_root: Vec<bool> = match root_finder {
+        RootFinder::AdditiveFft => evaluate_everywhere(poly, field)
+            .iter()
+            .map(|&value| value == 0)
+            .collect(),
+        RootFinder::BerlekampTrace => {
+            let mut is_root = vec![false; 1 << field.get_m()];
+            for root in berlekamp_trace_roots(poly, field) {
+                is_root[root as usize] = true;
+            }
+            is_root
+        }
+    };
+    support
+        .iter()
+        .enumerate()
+        .filter(|&(_, &alpha)| is_root[alpha as usize])
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_poly(degree: usize, field: &FiniteField, rng: &mut StdRng) -> Poly {
+        let order = field.multiplicative_order() as u32;
+        let mut coeffs: Vec<u32> = (0..degree).map(|_| rng.random_range(0..=order)).collect();
+        coeffs.push(rng.random_range(1..=order));
+        Poly::new(coeffs)
+    }
+
+    // Monic polynomial with exactly the given roots, all simple
+    fn from_roots(roots: &[u32], field: &FiniteField) -> Poly {
+        roots.iter().fold(Poly::one(), |acc, &root| {
+            acc.mul(&Poly::new(vec![root, 1]), field)
+        })
+    }
+
+    fn brute_force_roots(poly: &Poly, field: &FiniteField) -> Vec<u32> {
+        (0..=field.multiplicative_order() as u32)
+            .filter(|&x| poly.eval(x, field) == 0)
+            .collect()
+    }
+
+    // Random linearly independent elements, so their span has 2^dimension distinct points
+    fn random_basis(dimension: usize, field: &FiniteField, rng: &mut StdRng) -> Vec<u32> {
+        let mut basis = Vec::new();
+        while basis.len() < dimension {
+            let candidate = rng.random_range(1..=field.multiplicative_order() as u32);
+            if !span(&basis).contains(&candidate) {
+                basis.push(candidate);
+            }
+        }
+        basis
+    }
+
+    #[test]
+    fn evaluate_everywhere_matches_horner() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for m in 2..=10 {
+            let field = FiniteField::new(m).unwrap();
+            let size = 1 << m;
+            let mut polys = vec![Poly::zero(), Poly::constant(3 % size as u32)];
+            for degree in [1, 2, 3, 7, size - 1, size, size + 5] {
+                polys.push(random_poly(degree, &field, &mut rng));
+            }
+            for poly in polys {
+                let values = evaluate_everywhere(&poly, &field);
+                assert_eq!(values.len(), size);
+                for (x, &value) in values.iter().enumerate() {
+                    assert_eq!(
+                        value,
+                        poly.eval(x as u32, &field),
+                        "f({}) in GF(2^{})",
+                        x,
+                        m
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn additive_fft_matches_horner_on_any_basis() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for m in 2..=9 {
+            let field = FiniteField::new(m).unwrap();
+            for dimension in 0..=m as usize {
+                let basis = random_basis(dimension, &field, &mut rng);
+                let poly = random_poly(rng.random_range(0..40), &field, &mut rng);
+                let values = additive_fft(&poly, &basis, &field);
+                for (point, value) in span(&basis).into_iter().zip(values) {
+                    assert_eq!(value, poly.eval(point, &field));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn berlekamp_trace_finds_every_root() {
+        let mut rng = StdRng::seed_from_u64(22);
+        for m in 2..=10 {
+            let field = FiniteField::new(m).unwrap();
+            for _ in 0..20 {
+                // Random polynomials mostly have few roots, so also plant some
+                let poly = random_poly(rng.random_range(1..12), &field, &mut rng);
+                assert_eq!(
+                    berlekamp_trace_roots(&poly, &field),
+                    brute_force_roots(&poly, &field)
+                );
+
+                let mut roots: Vec<u32> = (0..rng.random_range(1..8))
+                    .map(|_| rng.random_range(0..=field.multiplicative_order() as u32))
+                    .collect();
+                roots.sort_unstable();
+                roots.dedup();
+                let planted = from_roots(&roots, &field).mul(&poly, &field);
+                assert_eq!(
+                    berlekamp_trace_roots(&planted, &field),
+                    brute_force_roots(&planted, &field)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn berlekamp_trace_edge_cases() {
+        let field = FiniteField::new(4).unwrap();
+        assert!(berlekamp_trace_roots(&Poly::zero(), &field).is_empty());
+        assert!(berlekamp_trace_roots(&Poly::constant(7), &field).is_empty());
+        assert_eq!(
+            berlekamp_trace_roots(&Poly::new(vec![5, 3]), &field),
+            vec![field.field_multiply(5, field.inverse(3).unwrap())]
+        );
+
+        // Repeated roots are reported once, and z^16 + z vanishes on the whole field
+        let squared = from_roots(&[2, 2, 9], &field);
+        assert_eq!(berlekamp_trace_roots(&squared, &field), vec![2, 9]);
+        let everything = Poly::monomial(1, 16).add(&Poly::z());
+        assert_eq!(
+            berlekamp_trace_roots(&everything, &field),
+            (0..16).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn root_finders_agree_on_the_support() {
+        let mut rng = StdRng::seed_from_u64(23);
+        for m in [4, 6, 8] {
+            let field = FiniteField::new(m).unwrap();
+            let support: Vec<u32> = (0..(1 << m) - 3).map(|i| (i * 7 + 3) % (1 << m)).collect();
+            for _ in 0..20 {
+                let roots: Vec<u32> = (0..rng.random_range(0..6))
+                    .map(|_| support[rng.random_range(0..support.len())])
+                    .collect();
+                let poly =
+                    from_roots(&roots, &field).mul(&random_poly(3, &field, &mut rng), &field);
+                let expected: Vec<usize> = (0..support.len())
+                    .filter(|&i| poly.eval(support[i], &field) == 0)
+                    .collect();
+                for root_finder in [RootFinder::AdditiveFft, RootFinder::BerlekampTrace] {
+                    assert_eq!(
+                        find_support_roots(&poly, &support, &field, root_finder),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
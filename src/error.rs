@@ -0,0 +1,19 @@
+use crate::types::PqcatError;
+use std::error::Error;
+use std::fmt;
+
+impl fmt::Display for PqcatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PqcatError::InvalidParameters(message)
+            | PqcatError::Unsupported(message)
+            | PqcatError::GenerationFailed(message)
+            | PqcatError::Undecodable(message) => write!(f, "{}", message),
+            PqcatError::BudgetExhausted { iterations } => {
+                write!(f, "no error vector found within {} iterations", iterations)
+            }
+        }
+    }
+}
+
+impl Error for PqcatError {}
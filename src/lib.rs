@@ -1,28 +1,40 @@
 pub mod algorithm_runner;
 pub mod code_generator;
+pub mod error;
 pub mod algorithms {
     pub mod algorithm_utils;
     pub mod ball_collision;
+    pub mod bit_flipping;
     pub mod bjmm;
     pub mod config;
+    pub mod decoder;
+    pub mod dumer;
     pub mod lee_brickell;
     pub mod metrics;
     pub mod mmt;
     pub mod patterson;
     pub mod prange;
+    pub mod reaction_attack;
+    pub mod registry;
     pub mod stern;
+    pub mod sugiyama;
 }
 
 pub mod codes {
+    pub mod circulant;
     pub mod code_utils;
     pub mod goppa;
+    pub mod matrix_utils;
     pub mod polynomial_utils;
+    pub mod qc_mdpc;
+    pub mod root_finding;
 }
 
 pub mod benchmarks {
     pub mod benchmark_runner;
     pub mod benchmark_utils;
     pub mod config;
+    pub mod dfr;
 }
 
 pub mod types;
@@ -1,146 +1,168 @@
-use clap::{Parser, Subcommand};
+use clap::builder::{PossibleValuesParser, RangedU64ValueParser};
+use clap::{Arg, ArgMatches, Command};
 mod algorithm_runner;
 mod algorithms;
 mod benchmarks;
 mod code_generator;
 mod codes;
+mod error;
 mod types;
 
-use algorithm_runner::run_algorithm;
-use types::{CodeParams, PartitionParams};
+use algorithm_runner::{run_algorithm, run_reaction_attack};
+use algorithms::registry::{ALGORITHMS, find_algorithm};
+use std::process;
+use types::{AlgorithmSpec, CodeParams, PartitionParams, PqcatError};
 
-#[derive(Parser)]
-#[command(name = "pqcat")]
-#[command(about = "Run classical attacks on code-based cryptosystems", long_about = None)]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
+fn usize_arg(name: &'static str, default: usize, help: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .default_value(default.to_string())
+        .value_parser(clap::value_parser!(usize))
+        .help(help)
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    Prange {
-        #[arg(short, long, default_value_t = 15)]
-        n: usize, // Codeword length (number of bits)
-        #[arg(short, long, default_value_t = 11)]
-        k: usize, // Message length (number of bits)
-        #[arg(short, long, default_value_t = 1)]
-        w: usize, // Weight of the error vector (number of errors)
-        #[arg(short, long, default_value = "hamming")]
-        code_type: String, // Type of code: "random", "hamming", or "goppa"
-    },
-    Stern {
-        #[arg(short, long, default_value_t = 15)]
-        n: usize,
-        #[arg(short, long, default_value_t = 11)]
-        k: usize,
-        #[arg(short, long, default_value_t = 1)]
-        w: usize,
-        #[arg(short, long, default_value = "hamming")]
-        code_type: String,
-    },
-    LeeBrickell {
-        #[arg(short, long, default_value_t = 23)]
-        n: usize,
-        #[arg(short, long, default_value_t = 12)]
-        k: usize,
-        #[arg(short, long, default_value_t = 3)]
-        w: usize,
-        #[arg(short, long, default_value = "random")]
-        code_type: String,
-    },
-    BallCollision {
-        #[arg(short, long, default_value_t = 23)]
-        n: usize,
-        #[arg(short, long, default_value_t = 12)]
-        k: usize,
-        #[arg(short, long, default_value_t = 3)]
-        w: usize,
-        #[arg(short, long, default_value = "random")]
-        code_type: String,
-    },
-    Mmt {
-        #[arg(short, long, default_value_t = 31)]
-        n: usize,
-        #[arg(short, long, default_value_t = 15)]
-        k: usize,
-        #[arg(short, long, default_value_t = 4)]
-        w: usize,
-        #[arg(short, long, default_value = "random")]
-        code_type: String,
-        #[arg(short, long, default_value_t = 2)]
-        p: usize,
-        #[arg(long, default_value_t = 256)]
-        l1: usize, // Error split 1
-        #[arg(long, default_value_t = 256)]
-        l2: usize, // Error split 2
-    },
-    Bjmm {
-        #[arg(short, long, default_value_t = 23)]
-        n: usize,
-        #[arg(short, long, default_value_t = 12)]
-        k: usize,
-        #[arg(short, long, default_value_t = 3)]
-        w: usize,
-        #[arg(short, long, default_value = "random")]
-        code_type: String,
-    },
-    Patterson {
-        #[arg(short, long, default_value_t = 31)]
-        n: usize,
-        #[arg(short, long, default_value_t = 16)]
-        k: usize,
-        #[arg(short, long, default_value_t = 3)]
-        w: usize,
-        // Code type is fixed to "goppa" for Patterson
-    },
+fn code_args(command: Command, (n, k, w): (usize, usize, usize)) -> Command {
+    command
+        .arg(usize_arg("n", n, "Codeword length (number of bits)").short('n'))
+        .arg(usize_arg("k", k, "Message length (number of bits)").short('k'))
+        .arg(usize_arg("w", w, "Weight of the error vector (number of errors)").short('w'))
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_parser(clap::value_parser!(u64))
+                .help("Seed of all random choices, to replay a run (random if not given)"),
+        )
 }
 
-fn main() {
-    let cli = Cli::parse();
+// Subcommand of a registered algorithm: the code parameters, a code type if it supports more than
+// one, and its tunable parameters with their defaults and ranges
+fn algorithm_command(spec: &'static AlgorithmSpec) -> Command {
+    let cli_name = spec.name.replace('_', "-");
+    let mut command =
+        code_args(Command::new(cli_name.clone()), spec.default_code).about(spec.about);
+    if cli_name != spec.name {
+        command = command.alias(spec.name);
+    }
 
-    match cli.command {
-        Commands::Prange { n, k, w, code_type } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("prange", code_params, None);
-        }
-        Commands::Stern { n, k, w, code_type } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("stern", code_params, None);
-        }
-        Commands::LeeBrickell { n, k, w, code_type } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("lee_brickell", code_params, None);
-        }
-        Commands::BallCollision { n, k, w, code_type } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("ball_collision", code_params, None);
-        }
-        Commands::Mmt {
-            n,
-            k,
-            w,
-            code_type,
-            p,
-            l1,
-            l2,
-        } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            let partition_params = PartitionParams {
-                p: Some(p),
-                l1: Some(l1),
-                l2: Some(l2),
-            };
-            run_algorithm("mmt", code_params, Some(partition_params));
-        }
-        Commands::Bjmm { n, k, w, code_type } => {
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("bjmm", code_params, None);
+    if spec.code_types.len() > 1 {
+        command = command.arg(
+            Arg::new("code_type")
+                .short('c')
+                .long("code-type")
+                .default_value(spec.code_types[0])
+                .value_parser(PossibleValuesParser::new(spec.code_types.iter().copied()))
+                .help("Type of code"),
+        );
+    }
+
+    if spec.code_types.contains(&"qc") {
+        command = command.arg(
+            Arg::new("d")
+                .short('d')
+                .long("d")
+                .value_parser(clap::value_parser!(usize))
+                .help("Weight of each circulant of a QC code, odd (scaled like BIKE if not given)"),
+        );
+    }
+
+    for param in spec.params {
+        let mut arg = Arg::new(param.name)
+            .long(param.name)
+            .default_value(param.default.to_string())
+            .value_parser(
+                RangedU64ValueParser::<usize>::new()
+                    .range(*param.range.start() as u64..=*param.range.end() as u64),
+            )
+            .help(param.help);
+        if let [short] = param.name.as_bytes() {
+            arg = arg.short(*short as char);
         }
-        Commands::Patterson { n, k, w } => {
-            let code_type = "goppa".to_string();
-            let code_params = CodeParams { n, k, w, code_type };
-            run_algorithm("patterson", code_params, None);
+        command = command.arg(arg);
+    }
+
+    command
+}
+
+fn reaction_attack_command() -> Command {
+    code_args(Command::new("reaction-attack"), (1046, 523, 32))
+        .about("GJS reaction attack recovering a QC-MDPC private key from decryption failures")
+        .arg(usize_arg("d", 5, "Weight of each secret circulant").short('d'))
+        .arg(
+            Arg::new("decoder")
+                .long("decoder")
+                .default_value("bgf")
+                .value_parser(["gallager", "bgf", "step_by_step"])
+                .help("Decoder behind the oracle"),
+        )
+        .arg(usize_arg("queries", 1000, "Oracle queries per distance").short('q'))
+}
+
+fn cli() -> Command {
+    let algorithms = ALGORITHMS.iter().map(algorithm_command);
+    Command::new("pqcat")
+        .about("Run classical attacks on code-based cryptosystems")
+        .subcommand_required(true)
+        .subcommands(algorithms)
+        .subcommand(reaction_attack_command())
+}
+
+fn code_params(matches: &ArgMatches, code_type: &str) -> CodeParams {
+    CodeParams {
+        n: matches.get_one::<usize>("n").copied().unwrap_or_default(),
+        k: matches.get_one::<usize>("k").copied().unwrap_or_default(),
+        w: matches.get_one::<usize>("w").copied().unwrap_or_default(),
+        code_type: code_type.to_string(),
+        circulant_weight: None,
+    }
+}
+
+// Runs that could not start exit with status 1, which the benchmark runner relies on to skip them
+fn exit_on_error(result: Result<(), PqcatError>) {
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let matches = cli().get_matches();
+    let Some((name, matches)) = matches.subcommand() else {
+        return;
+    };
+
+    if name == "reaction-attack" {
+        let code_params = code_params(matches, "qc");
+        let d = matches.get_one::<usize>("d").copied().unwrap_or_default();
+        let decoder = matches.get_one::<String>("decoder").unwrap();
+        let queries = matches
+            .get_one::<usize>("queries")
+            .copied()
+            .unwrap_or_default();
+        let seed = matches.get_one::<u64>("seed").copied();
+        exit_on_error(run_reaction_attack(code_params, d, decoder, queries, seed));
+        return;
+    }
+
+    let spec = find_algorithm(name).expect("every subcommand comes from the registry");
+    // Only algorithms supporting several code types register the code_type argument
+    let code_type = match spec.code_types {
+        [only] => *only,
+        _ => matches.get_one::<String>("code_type").unwrap().as_str(),
+    };
+    let mut partition_params = PartitionParams::default();
+    for param in spec.params {
+        if let Some(&value) = matches.get_one::<usize>(param.name) {
+            partition_params.set(param.name, value);
         }
     }
+    let mut code_params = code_params(matches, code_type);
+    if spec.code_types.contains(&"qc") {
+        code_params.circulant_weight = matches.get_one::<usize>("d").copied();
+    }
+    exit_on_error(run_algorithm(
+        spec.name,
+        code_params,
+        Some(partition_params),
+        matches.get_one::<u64>("seed").copied(),
+    ));
 }
@@ -1,76 +1,333 @@
+use crate::algorithms::decoder::Decoder;
+use crate::algorithms::metrics::AlgorithmMetrics;
+use std::ops::RangeInclusive;
+
 // FiniteField implementation for field element operations
 #[derive(Clone)]
 pub struct FiniteField {
-    pub m: u8,     // Extension degree (field is GF(2^m))
-    pub poly: u32, // Irreducible polynomial represented as a bit pattern
+    pub m: u8,                      // Extension degree (field is GF(2^m))
+    pub poly: u32,                  // Irreducible polynomial represented as a bit pattern
+    pub generator: u32,             // Primitive element the log/antilog tables are built on
+    pub(crate) exp_table: Vec<u32>, // exp_table[i] = generator^i, stored twice so log sums need no reduction
+    pub(crate) log_table: Vec<u32>, // log_table[a] = i with generator^i = a (entry 0 unused)
+}
+
+// Polynomial over GF(2^m), coefficients lowest degree first with no trailing zeros (zero is empty)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Poly {
+    pub coeffs: Vec<u32>,
+}
+
+// Bit-packed vector over GF(2), bit i lives in words[i / 64] at position i % 64
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BinaryVector {
+    pub len: usize,
+    pub words: Vec<u64>,
+}
+
+// Element of the ring F2[x]/(x^r - 1), i.e. an r x r circulant matrix stored as its first row
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Circulant {
+    Dense(BinaryVector), // All r coefficients bit-packed, len = r
+    Sparse { r: usize, support: Vec<usize> }, // Sorted positions of the non-zero coefficients
+}
+
+// Bit-packed matrix over GF(2) stored row by row, each row padded to whole u64 words
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BinaryMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub words_per_row: usize,
+    pub data: Vec<u64>,
+}
+
+// Result of bringing a parity-check matrix to systematic form by Gaussian elimination
+#[allow(dead_code)]
+pub struct SystematicForm {
+    pub g: BinaryMatrix,         // Generator matrix [I_k | P]
+    pub h: BinaryMatrix,         // Parity-check matrix [P^T | I_(n-k)], zero rows removed
+    pub permutation: Vec<usize>, // Column i of G and H is column permutation[i] of the input H
+    pub dimension: usize,        // True code dimension k = n - rank(H)
+}
+
+// Kind of Goppa polynomial g(z) to draw when generating a Goppa code
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GoppaPolyKind {
+    Irreducible, // Classic binary Goppa code, what Patterson's guarantees rely on
+    SquareFree,  // Square-free but reducible, for experiments
+}
+
+// Modulus of the field GF(2^m) a Goppa code is defined over
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GoppaField {
+    Standard,        // The default primitive polynomial of FiniteField::new
+    ClassicMcEliece, // The fields fixed by Classic McEliece, only defined for m = 12 and 13
 }
 
 #[derive(Clone)]
 pub struct GoppaParams {
     pub field: FiniteField,
-    pub goppa_poly: Vec<u32>,
+    pub goppa_poly: Poly,
     pub support: Vec<u32>,
     pub t: usize,
 }
 
+// QC-MDPC private key: H = [H0 | H1 | ... | H_(index-1)], each block a sparse r x r circulant
+#[derive(Clone)]
+pub struct QcMdpcKey {
+    pub r: usize,
+    pub circulant_weight: usize,
+    pub blocks: Vec<Circulant>,
+}
+
+// Secret structure behind a generated code, for the decoders that need more than H
+#[derive(Clone)]
+pub enum StructuredKey {
+    Goppa(GoppaParams),
+    QcMdpc(QcMdpcKey),
+}
+
+// Flipping rule of a bit-flipping decoder for QC-MDPC codes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlipRule {
+    Gallager,      // Flip every bit with the maximal counter
+    BlackGrayFlip, // BIKE's BGF decoder
+    StepByStep,    // Flip one bit of a random unsatisfied check at a time
+}
+
+// How an algebraic decoder finds the roots of its error locator on the code support
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RootFinder {
+    AdditiveFft,    // Evaluate on the whole field at once with the Gao-Mateer FFT
+    BerlekampTrace, // Factor the locator with trace polynomials, cheaper for sparse supports
+}
+
+// Word handed to a decoder: a received vector r = c + e, or the syndrome H e itself
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub enum DecodeTarget<'a> {
+    Received(&'a [u8]),
+    Syndrome(&'a BinaryVector),
+}
+
+// Common input of every decoder behind the `Decoder` trait
+#[derive(Clone, Copy)]
+pub struct DecodeInput<'a> {
+    pub h: &'a BinaryMatrix,
+    pub target: DecodeTarget<'a>,
+    pub weight: usize,                  // Number of errors to look for
+    pub key: Option<&'a StructuredKey>, // Secret structure, for the decoders that need it
+}
+
+// Errors of the library's code generators, codes and algorithms
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PqcatError {
+    InvalidParameters(String), // Parameters outside what the code, field or algorithm allows
+    Unsupported(String), // A valid request this library does not handle, e.g. a decoder without its key
+    GenerationFailed(String), // Random generation gave up after its attempts
+    BudgetExhausted { iterations: usize }, // A probabilistic or iterative decoder ran out of iterations
+    Undecodable(String), // No error vector of the target weight explains the syndrome
+}
+
+// Error vector found by a decoder, with the metrics of the run whether it succeeded or not
+pub struct DecodeOutcome {
+    pub result: Result<Vec<u8>, PqcatError>,
+    pub metrics: AlgorithmMetrics,
+}
+
+// Decoders implementing the `Decoder` trait, each carrying its tunable parameters
+pub struct Prange;
+
+pub struct Stern {
+    pub p: usize, // Error weight in each half of the information set
+    pub l: usize, // Size of the collision window
+}
+
+pub struct Dumer {
+    pub p: usize, // Error weight in each half of the (k+l)-sized window
+    pub l: usize, // Number of syndrome bits matched by sorting
+}
+
+pub struct LeeBrickell {
+    pub p: usize, // Number of errors guessed inside the information set
+}
+
+pub struct BallCollision;
+
+pub struct Mmt {
+    pub p: usize,
+    pub l1: usize, // Bits fixed by the first merge
+    pub l2: usize, // Bits fixed by the second merge
+}
+
+pub struct Bjmm {
+    pub p: usize,
+    pub eps1: usize, // Overlapping positions on level 1
+    pub eps2: usize, // Overlapping positions on level 2
+    pub l1: usize,   // Bits fixed on level 1
+    pub l2: usize,   // Bits fixed on level 2
+}
+
+pub struct Patterson {
+    pub root_finder: RootFinder,
+}
+
+pub struct Sugiyama;
+
+pub struct BitFlipping {
+    pub rule: FlipRule,
+}
+
+// Alternant code over GF(2^m): H[i][j] = multipliers[j] * support[j]^i for i < r, correcting r/2 errors
+#[derive(Clone)]
+pub struct AlternantParams {
+    pub field: FiniteField,
+    pub support: Vec<u32>,
+    pub multipliers: Vec<u32>,
+    pub r: usize,
+}
+
 #[derive(Clone)]
 pub struct CodeParams {
     pub n: usize,
     pub k: usize,
     pub w: usize,
     pub code_type: String,
+    pub circulant_weight: Option<usize>, // Weight d of each QC-MDPC circulant, BIKE-scaled if not set
 }
 
-#[derive(Clone)]
+// Algorithm parameters; the ones left at None take the defaults declared in the registry
+#[derive(Clone, Default)]
 pub struct PartitionParams {
     pub p: Option<usize>,
+    pub l: Option<usize>, // Collision window size (Stern, Dumer)
     pub l1: Option<usize>,
     pub l2: Option<usize>,
+    pub eps1: Option<usize>, // Extra overlapping positions per level (BJMM)
+    pub eps2: Option<usize>,
 }
 
-impl Default for PartitionParams {
-    fn default() -> Self {
-        Self {
-            p: Some(2),
-            l1: Some(1),
-            l2: Some(1),
-        }
-    }
+// Tunable parameter of an algorithm, one of the fields of PartitionParams. All of them are counts
+// (error weights, window sizes in bits), so values are usize
+pub struct ParamSpec {
+    pub name: &'static str, // Field of PartitionParams, also the CLI flag --<name>
+    pub help: &'static str,
+    pub default: usize,
+    pub range: RangeInclusive<usize>,
+}
+
+// Registry entry of a decoding algorithm, from which the CLI, the benchmark commands and the bulk
+// runners are derived
+pub struct AlgorithmSpec {
+    pub name: &'static str, // Registry name, the CLI subcommand is the same with dashes
+    pub about: &'static str,
+    pub default_code: (usize, usize, usize), // Default (n, k, w) on the CLI
+    pub code_types: &'static [&'static str], // Supported code types, the first one is the default
+    pub params: &'static [ParamSpec],
+    pub build: fn(&PartitionParams) -> Box<dyn Decoder>, // Called with every parameter set
+}
+
+// How benchmark runs are executed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunIsolation {
+    InProcess,  // Decoders called through the library, timing only the decoding
+    Subprocess, // One pqcat process per run, for peak memory figures not shared with other runs
 }
 
 pub struct BenchmarkConfig {
     pub runs: usize,
+    pub isolation: RunIsolation,
+    pub seed: Option<u64>, // Seed the per-run seeds are drawn from, random if not set
     pub algorithm_name: String,
     pub n: usize,
     pub k: usize,
     pub w: usize,
     pub code_type: String,
-    // Optional parameters only for MMT
+    pub circulant_weight: Option<usize>, // Weight d of each QC-MDPC circulant, BIKE-scaled if not set
+    // Optional algorithm parameters (p for Stern, Dumer, Lee-Brickell, MMT and BJMM, l for Stern and Dumer,
+    // l1/l2 for MMT and BJMM, eps1/eps2 for BJMM)
     pub p: Option<usize>,
+    pub l: Option<usize>,
     pub l1: Option<usize>,
     pub l2: Option<usize>,
+    pub eps1: Option<usize>,
+    pub eps2: Option<usize>,
 }
 
 impl Default for BenchmarkConfig {
     fn default() -> Self {
         Self {
             runs: 100,
+            isolation: RunIsolation::InProcess,
+            seed: None,
             algorithm_name: "prange".to_string(),
             n: 15,
             k: 11,
             w: 1,
             code_type: "hamming".to_string(),
+            circulant_weight: None,
             p: None,
+            l: None,
             l1: None,
             l2: None,
+            eps1: None,
+            eps2: None,
         }
     }
 }
 
+// Decoding failure rate sweep of one bit-flipping decoder over prime block sizes r, at fixed
+// row weight w = l * d and error weight t
+pub struct DfrConfig {
+    pub decoder: FlipRule,
+    pub block_sizes: Vec<usize>,
+    pub index: usize,            // Number of circulant blocks l
+    pub circulant_weight: usize, // d, odd
+    pub t: usize,
+    pub decodings: usize, // Decodings per block size, split evenly over the keys
+    pub keys: usize,      // Private keys drawn per block size
+    pub max_recorded_failures: usize, // Failing error vectors kept per block size
+    pub target_r: Option<usize>, // Block size to extrapolate the fitted curve to
+    pub seed: Option<u64>, // Seed the per-block-size seeds are drawn from, random if not set
+}
+
+// Measured failure rate at one block size, with the failing error vectors as (key, support)
+pub struct DfrPoint {
+    pub r: usize,
+    pub seed: u64, // Replays the keys and decodings of this block size
+    pub decodings: usize,
+    pub failures: usize,
+    pub mean_iterations: f64,
+    pub recorded_failures: Vec<(usize, Vec<usize>)>,
+    pub keys: Vec<QcMdpcKey>,
+}
+
+// Least-squares line log10(DFR) = intercept + slope * r through the points with failures
+pub struct DfrFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub points_used: usize,
+}
+
+// Outcome of a reaction attack: the distance spectrum of h0 read off the oracle's failures and
+// the secret circulant rebuilt from it, if any
+pub struct ReactionAttackReport {
+    pub queries: usize,
+    pub failures: usize,
+    pub failure_rates: Vec<f64>, // Entry delta - 1: failure rate of errors with pairs at distance delta
+    pub spectrum: Vec<usize>,    // Distances classified as occurring in h0
+    pub spectrum_errors: usize,  // Distances classified wrongly, checked against the real key
+    pub recovered_h0: Option<Circulant>,
+    pub success: bool, // The recovered h0 is a rotation of the real one
+}
+
 pub struct BenchmarkResult {
+    pub seed: u64, // Replays the run, e.g. with the CLI's --seed
     pub duration: u64,
     pub memory: u64,
     pub success: bool,
+    pub iterations: u64,
+    pub success_probability: Option<f64>, // Theoretical per-iteration value reported by the decoder
 }
 
 pub struct BenchmarkStats {
@@ -83,4 +340,7 @@ pub struct BenchmarkStats {
     pub time_ci_upper: f64,
     pub memory_ci_lower: f64,
     pub memory_ci_upper: f64,
+    pub mean_iterations: f64,
+    pub measured_success_probability: f64, // Successful runs per iteration over all runs
+    pub theoretical_success_probability: Option<f64>,
 }